@@ -0,0 +1,110 @@
+/// Public sites an LLM needs to see as they are.
+pub const DEFAULT_ALLOWLIST: &[&str] = &[
+    "github.com",
+    "*.github.io",
+    "gist.github.com",
+    "gitlab.com",
+    "bitbucket.org",
+    "docs.rs",
+    "crates.io",
+    "*.rust-lang.org",
+    "stackoverflow.com",
+    "*.stackexchange.com",
+    "serverfault.com",
+    "superuser.com",
+    "developer.mozilla.org",
+    "*.wikipedia.org",
+    "npmjs.com",
+    "pypi.org",
+    "docs.python.org",
+    "go.dev",
+    "kubernetes.io",
+    "docs.docker.com",
+    "hub.docker.com",
+    "learn.microsoft.com",
+    "docs.aws.amazon.com",
+    "cloud.google.com",
+    "slack.com",
+    "api.slack.com",
+];
+
+/// Domains whose URLs and emails are left intact.
+///
+/// `github.com` allows that host (and `www.github.com`), `*.atlassian.net`
+/// allows `atlassian.net` and every subdomain. Entries given as URLs or emails
+/// (`https://docs.rs/regex`, `noreply@github.com`) allow their domain.
+#[derive(Debug, Clone, Default)]
+pub struct Allowlist {
+    hosts: Vec<String>,
+    suffixes: Vec<String>,
+}
+
+impl Allowlist {
+    pub fn new<S: AsRef<str>>(entries: impl IntoIterator<Item = S>) -> Self {
+        let mut allowlist = Self::default();
+        for entry in entries {
+            let entry = host_of(entry.as_ref().trim()).to_lowercase();
+            if let Some(suffix) = entry.strip_prefix("*.") {
+                if !suffix.is_empty() {
+                    allowlist.suffixes.push(suffix.to_string());
+                }
+            } else if !entry.is_empty() {
+                allowlist.hosts.push(entry);
+            }
+        }
+        allowlist
+    }
+
+    /// Whether the host of a URL, or the domain of an email, is allowed.
+    pub fn allows(&self, value: &str) -> bool {
+        let host = host_of(value).to_lowercase();
+
+        self.hosts.contains(&host)
+            || self.suffixes.iter().any(|suffix| {
+                host == *suffix
+                    || host
+                        .strip_suffix(suffix.as_str())
+                        .is_some_and(|rest| rest.ends_with('.'))
+            })
+    }
+}
+
+// `https://user@docs.rs:443/regex` → `docs.rs`, `jon@corp.com` → `corp.com`
+fn host_of(value: &str) -> &str {
+    let rest = value.split_once("://").map_or(value, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    let host = host.split(':').next().unwrap_or(host);
+    host.strip_prefix("www.").unwrap_or(host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allows() {
+        let allowlist = Allowlist::new(["github.com", "*.atlassian.net", "https://docs.rs/regex"]);
+
+        assert!(allowlist.allows("https://github.com/rust-lang/rust"));
+        assert!(allowlist.allows("https://www.GitHub.com"));
+        assert!(allowlist.allows("noreply@github.com"));
+        assert!(allowlist.allows("https://acme.atlassian.net/browse/PROJ-1"));
+        assert!(allowlist.allows("http://docs.rs:80"));
+        assert!(!allowlist.allows("https://gist.github.com"));
+        assert!(!allowlist.allows("https://notgithub.com"));
+        assert!(!allowlist.allows("jon@corp.com"));
+    }
+
+    #[test]
+    fn test_default_allowlist() {
+        let allowlist = Allowlist::new(DEFAULT_ALLOWLIST);
+
+        assert!(allowlist.allows("https://stackoverflow.com/questions/1"));
+        assert!(allowlist.allows("https://doc.rust-lang.org/std"));
+        assert!(allowlist.allows("https://en.wikipedia.org/wiki/Slack"));
+        assert!(!allowlist.allows("https://acme.com"));
+    }
+}
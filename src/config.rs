@@ -0,0 +1,385 @@
+use crate::anonymizer::{Detector, Options};
+use crate::engine::Category;
+use crate::error::{AnonymizationError, ConfigError};
+use crate::masking::MaskRule;
+use crate::templates::template_categories;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File name of a project-local config, looked up in the working directory.
+pub const PROJECT_CONFIG: &str = ".slack-anonymizer.toml";
+
+/// Settings read from a TOML file, so the same flags and keyword lists don't
+/// have to be passed on every run.
+///
+/// ```toml
+/// keywords = ["ProjectX", "ClientABC"]
+/// owned_domains = ["acme.com"]
+/// allow = ["*.atlassian.net"]
+/// map_file = "vault.json"
+///
+/// [detectors]
+/// urls = true
+/// issues = true
+/// channels = false
+///
+/// [issues]
+/// pattern = "PAY|OPS"
+///
+/// [placeholders]
+/// user = "<PERSON_{n}>"
+///
+/// [masks]
+/// email = { domain = "keep" }
+/// phone = {}
+///
+/// [legend]
+/// show = true
+/// ```
+///
+/// Unset values keep their defaults, so a later file or a CLI flag only
+/// overrides what it sets.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub keywords: Vec<String>,
+    pub owned_domains: Vec<String>,
+    /// Added to the default allowlist
+    pub allow: Vec<String>,
+    /// Added to the default sensitive query keys
+    pub sensitive_query_keys: Vec<String>,
+    pub link_people: Option<bool>,
+    pub realistic_names: Option<bool>,
+    pub seed: Option<u64>,
+    /// Relative paths are resolved against the config file's directory
+    pub roster: Option<PathBuf>,
+    pub map_file: Option<PathBuf>,
+    /// File with the secret key of keyed pseudonyms, see [`Options::pseudonym_key`]
+    pub pseudonym_key_file: Option<PathBuf>,
+    pub detectors: DetectorsConfig,
+    pub issues: IssuesConfig,
+    /// Templates by category name, or `all`
+    pub placeholders: BTreeMap<String, String>,
+    /// Mask rules by category name, or `all`
+    pub masks: BTreeMap<String, MaskRule>,
+    /// Category names, or `all`, replaced by values of the same length and format
+    pub preserve_format: Vec<String>,
+    pub legend: LegendConfig,
+}
+
+/// Detectors switched on or off by name (`channels = false`, `urls = true`),
+/// or the only ones to run.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct DetectorsConfig {
+    pub only: Option<Vec<Detector>>,
+    #[serde(flatten)]
+    pub switches: BTreeMap<Detector, bool>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IssuesConfig {
+    pub pattern: Option<String>,
+    pub renumber: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LegendConfig {
+    /// Print the legend after the output
+    pub show: Option<bool>,
+    /// Print the values kept by the allowlist
+    pub report: Option<bool>,
+}
+
+impl Config {
+    pub fn from_toml(toml: &str) -> Result<Self, ConfigError> {
+        let config: Self =
+            toml::from_str(toml).map_err(|e| ConfigError::InvalidToml(e.to_string()))?;
+        let names = config
+            .placeholders
+            .keys()
+            .chain(config.masks.keys())
+            .chain(&config.preserve_format);
+        if let Some(name) = names
+            .into_iter()
+            .find(|name| template_categories(name).is_none())
+        {
+            return Err(ConfigError::InvalidToml(format!(
+                "unknown category '{}'",
+                name
+            )));
+        }
+        Ok(config)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, AnonymizationError> {
+        let path = path.as_ref();
+        let toml = fs::read_to_string(path)?;
+        let mut config = Self::from_toml(&toml)?;
+
+        let base = path.parent().unwrap_or(Path::new(""));
+        for file in [
+            &mut config.roster,
+            &mut config.map_file,
+            &mut config.pseudonym_key_file,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if file.is_relative() {
+                *file = base.join(&*file);
+            }
+        }
+        Ok(config)
+    }
+
+    /// Loads `explicit` if given. Otherwise merges the user config
+    /// (`$XDG_CONFIG_HOME/slack-anonymizer/config.toml`, or `~/.config/...`)
+    /// with the project-local [`PROJECT_CONFIG`], which wins. Missing files are skipped.
+    pub fn discover(explicit: Option<&Path>) -> Result<Self, AnonymizationError> {
+        if let Some(path) = explicit {
+            if !path.exists() {
+                return Err(ConfigError::NotFound(path.display().to_string()).into());
+            }
+            return Self::load(path);
+        }
+
+        let mut config = Self::default();
+        let candidates = user_config_path()
+            .into_iter()
+            .chain([PathBuf::from(PROJECT_CONFIG)]);
+        for path in candidates {
+            if path.exists() {
+                config = config.merge(Self::load(&path)?);
+            }
+        }
+        Ok(config)
+    }
+
+    /// `other` wins for single values; lists are combined.
+    pub fn merge(mut self, other: Config) -> Self {
+        self.keywords.extend(other.keywords);
+        self.owned_domains.extend(other.owned_domains);
+        self.allow.extend(other.allow);
+        self.sensitive_query_keys.extend(other.sensitive_query_keys);
+        self.link_people = other.link_people.or(self.link_people);
+        self.realistic_names = other.realistic_names.or(self.realistic_names);
+        self.seed = other.seed.or(self.seed);
+        self.roster = other.roster.or(self.roster);
+        self.map_file = other.map_file.or(self.map_file);
+        self.pseudonym_key_file = other.pseudonym_key_file.or(self.pseudonym_key_file);
+
+        self.detectors.only = other.detectors.only.or(self.detectors.only);
+        self.detectors.switches.extend(other.detectors.switches);
+        self.issues.pattern = other.issues.pattern.or(self.issues.pattern);
+        self.issues.renumber = other.issues.renumber.or(self.issues.renumber);
+        self.placeholders.extend(other.placeholders);
+        self.masks.extend(other.masks);
+        self.preserve_format.extend(other.preserve_format);
+        self.legend.show = other.legend.show.or(self.legend.show);
+        self.legend.report = other.legend.report.or(self.legend.report);
+        self
+    }
+
+    /// The anonymization options this config describes, defaults for the rest.
+    pub fn options(&self) -> Options {
+        let mut options = Options::new(false, self.keywords.clone());
+        options.link_people = self.link_people.unwrap_or(false);
+        options.realistic_names = self.realistic_names.unwrap_or(false);
+        if let Some(seed) = self.seed {
+            options.seed = seed;
+        }
+        options.owned_domains = self.owned_domains.clone();
+        options.allowlist.extend(self.allow.iter().cloned());
+        options
+            .sensitive_query_keys
+            .extend(self.sensitive_query_keys.iter().cloned());
+        if let Some(pattern) = &self.issues.pattern {
+            options.issue_key_pattern = pattern.clone();
+        }
+        options.keep_issue_numbers = !self.issues.renumber.unwrap_or(false);
+        if let Some(only) = &self.detectors.only {
+            options.only(only);
+        }
+        for (&detector, &enabled) in &self.detectors.switches {
+            options.set_enabled(detector, enabled);
+        }
+        for (category, template) in by_category(&self.placeholders) {
+            options.placeholders.insert(category, template.clone());
+        }
+        for (category, rule) in by_category(&self.masks) {
+            options.masks.insert(category, *rule);
+        }
+        for name in &self.preserve_format {
+            options
+                .preserve_format
+                .extend(template_categories(name).unwrap_or_default());
+        }
+        options
+    }
+}
+
+// `all` first, so single categories override it
+fn by_category<T>(values: &BTreeMap<String, T>) -> Vec<(Category, &T)> {
+    let (all, single): (Vec<_>, Vec<_>) = values
+        .iter()
+        .partition(|(name, _)| name.eq_ignore_ascii_case("all"));
+    all.into_iter()
+        .chain(single)
+        .flat_map(|(name, value)| {
+            template_categories(name)
+                .unwrap_or_default()
+                .into_iter()
+                .map(move |category| (category, value))
+        })
+        .collect()
+}
+
+fn user_config_path() -> Option<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(config_home.join("slack-anonymizer").join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::masking::DomainMask;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_options_from_toml() {
+        let config = Config::from_toml(
+            r#"
+            keywords = ["ProjectX", "ClientABC"]
+            allow = ["*.atlassian.net"]
+
+            [detectors]
+            urls = true
+            issues = true
+            display_names = false
+
+            [issues]
+            pattern = "PAY|OPS"
+            "#,
+        )
+        .unwrap();
+        let options = config.options();
+
+        assert!(options.anonymize_urls);
+        assert_eq!(options.keywords, vec!["ProjectX", "ClientABC"]);
+        assert!(options.allowlist.contains(&"*.atlassian.net".to_string()));
+        assert!(options.allowlist.contains(&"github.com".to_string()));
+        assert!(options.issue_keys);
+        assert_eq!(options.issue_key_pattern, "PAY|OPS");
+        assert!(!options.repo_refs);
+        assert!(!options.enabled(Detector::DisplayNames));
+        assert!(options.enabled(Detector::Channels));
+    }
+
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        assert!(Config::from_toml("keyword = [\"typo\"]").is_err());
+        assert!(Config::from_toml("[detectors]\nchanels = false").is_err());
+        assert!(Config::from_toml("[detectors]\nonly = [\"mails\"]").is_err());
+    }
+
+    #[test]
+    fn test_placeholders() {
+        let config =
+            Config::from_toml("[placeholders]\nuser = \"<PERSON_{n}>\"\nall = \"[REDACTED]\"")
+                .unwrap();
+        let options = config.options();
+
+        assert_eq!(options.placeholders[&Category::User], "<PERSON_{n}>");
+        assert_eq!(options.placeholders[&Category::Email], "[REDACTED]");
+        assert!(Config::from_toml("[placeholders]\nperson = \"<P>\"").is_err());
+    }
+
+    #[test]
+    fn test_masks() {
+        let config =
+            Config::from_toml("[masks]\nall = {}\nemail = { keep_start = 2, domain = \"keep\" }")
+                .unwrap();
+        let options = config.options();
+
+        assert_eq!(options.masks[&Category::Phone], MaskRule::default());
+        assert_eq!(options.masks[&Category::Email].keep_start, Some(2));
+        assert_eq!(
+            options.masks[&Category::Email].domain,
+            Some(DomainMask::Keep)
+        );
+        assert!(Config::from_toml("[masks]\nemail = { keep = 2 }").is_err());
+        assert!(Config::from_toml("[masks]\nmails = {}").is_err());
+    }
+
+    #[test]
+    fn test_preserve_format() {
+        let config = Config::from_toml("preserve_format = [\"ip\", \"phone\"]").unwrap();
+        let options = config.options();
+
+        assert_eq!(
+            options.preserve_format.into_iter().collect::<Vec<_>>(),
+            vec![Category::Phone, Category::Ip]
+        );
+        let all = Config::from_toml("preserve_format = [\"all\"]").unwrap();
+        assert_eq!(all.options().preserve_format.len(), Category::ALL.len());
+        assert!(Config::from_toml("preserve_format = [\"ips\"]").is_err());
+    }
+
+    #[test]
+    fn test_only_detectors() {
+        let config =
+            Config::from_toml("[detectors]\nonly = [\"emails\", \"urls\"]\nsecrets = true")
+                .unwrap();
+        let options = config.options();
+        let enabled: Vec<Detector> = Detector::ALL
+            .iter()
+            .copied()
+            .filter(|&detector| options.enabled(detector))
+            .collect();
+
+        assert_eq!(
+            enabled,
+            vec![Detector::Emails, Detector::Urls, Detector::Secrets]
+        );
+    }
+
+    #[test]
+    fn test_merge_and_relative_paths() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(
+            &path,
+            "keywords = [\"ClientABC\"]\nmap_file = \"vault.json\"\n[detectors]\nurls = false\n",
+        )
+        .unwrap();
+
+        let user =
+            Config::from_toml("keywords = [\"ProjectX\"]\n[detectors]\nurls = true\nrepos = true")
+                .unwrap();
+        let config = user.merge(Config::load(&path).unwrap());
+
+        assert_eq!(config.keywords, vec!["ProjectX", "ClientABC"]);
+        assert!(!config.detectors.switches[&Detector::Urls]);
+        assert!(config.detectors.switches[&Detector::Repos]);
+        assert_eq!(config.map_file, Some(dir.path().join("vault.json")));
+    }
+
+    #[test]
+    fn test_explicit_config_must_exist() {
+        let result = Config::discover(Some(Path::new("/nonexistent/config.toml")));
+
+        assert!(matches!(
+            result,
+            Err(AnonymizationError::Config(ConfigError::NotFound(_)))
+        ));
+    }
+}
@@ -0,0 +1,224 @@
+use crate::engine::{Category, Span};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+// Dot-separated labels; `OwnedDomains` decides which hostnames are anonymized
+const HOSTNAME_PATTERN: &str =
+    r"(?i)\b(?:[a-z0-9](?:[a-z0-9-]{0,61}[a-z0-9])?\.)+[a-z0-9](?:[a-z0-9-]{0,61}[a-z0-9])?\b";
+
+static HOSTNAME_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(HOSTNAME_PATTERN).unwrap());
+
+/// Domain suffixes the organization owns, e.g. `acme.com`, `acme.atlassian.net`
+/// or `internal`. Every hostname under them is anonymized, also in emails and URLs.
+#[derive(Debug, Clone, Default)]
+pub struct OwnedDomains {
+    // Longest first, so `corp.acme.net` wins over `acme.net`
+    suffixes: Vec<String>,
+}
+
+impl OwnedDomains {
+    /// Accepts `acme.com`, `.acme.com` and `*.acme.com` alike.
+    pub fn new<S: AsRef<str>>(suffixes: impl IntoIterator<Item = S>) -> Self {
+        let mut suffixes: Vec<String> = suffixes
+            .into_iter()
+            .map(|suffix| {
+                let suffix = suffix.as_ref().trim().to_lowercase();
+                suffix
+                    .trim_start_matches("*.")
+                    .trim_matches('.')
+                    .to_string()
+            })
+            .filter(|suffix| !suffix.is_empty())
+            .collect();
+        suffixes.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        suffixes.dedup();
+
+        Self { suffixes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.suffixes.is_empty()
+    }
+
+    /// The owned suffix `host` is or belongs to.
+    pub fn root_of(&self, host: &str) -> Option<&str> {
+        let host = host.to_lowercase();
+        self.suffixes
+            .iter()
+            .find(|suffix| {
+                host == **suffix
+                    || host
+                        .strip_suffix(suffix.as_str())
+                        .is_some_and(|rest| rest.ends_with('.'))
+            })
+            .map(String::as_str)
+    }
+}
+
+/// Finds bare hostnames under an owned suffix (`db-prod-3.eu.corp.acme.net`,
+/// `grafana.internal`). Other hostnames and file names are left alone.
+pub fn detect_hostnames(text: &str, owned: &OwnedDomains) -> Vec<Span> {
+    if owned.is_empty() {
+        return Vec::new();
+    }
+
+    HOSTNAME_RE
+        .find_iter(text)
+        .filter(|m| owned.root_of(m.as_str()).is_some())
+        .map(|m| Span::new(m.range(), Category::Host, vec![m.as_str().to_lowercase()]))
+        .collect()
+}
+
+/// Anonymizes `host` if it is owned: the owned suffix becomes `example.<tld>`
+/// (`example2.<tld>`, ... for the next ones) and every hostname under it
+/// `hostN.example.<tld>`. The mapping is shared by hostnames, emails and URLs.
+pub(crate) fn owned_host_placeholder(
+    host: &str,
+    owned: &OwnedDomains,
+    map: &mut HashMap<String, String>,
+    counts: &mut HostCounts,
+) -> Option<String> {
+    let root = owned.root_of(host)?;
+    let host = host.to_lowercase();
+    if let Some(anonymous) = map.get(&host) {
+        return Some(anonymous.clone());
+    }
+
+    counts.sync(map);
+    let fake_root = match map.get(root) {
+        Some(fake_root) => fake_root.clone(),
+        None => {
+            let number = counts.roots.len() + 1;
+            let tld = root.rsplit('.').next().unwrap_or(root);
+            let fake_root = match number {
+                1 => format!("example.{}", tld),
+                _ => format!("example{}.{}", number, tld),
+            };
+            map.insert(root.to_string(), fake_root.clone());
+            counts.record(&fake_root, map);
+            fake_root
+        }
+    };
+    if host == root {
+        return Some(fake_root);
+    }
+
+    let hosts = counts.hosts.get(&fake_root).copied().unwrap_or(0);
+    let anonymous = format!("host{}.{}", hosts + 1, fake_root);
+    map.insert(host, anonymous.clone());
+    counts.record(&anonymous, map);
+    Some(anonymous)
+}
+
+/// The fake roots of a domains map and the hosts under each, kept next to it
+/// like [`Taken`](crate::patterns::Taken) so a new host doesn't rescan the map.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct HostCounts {
+    roots: HashSet<String>,
+    hosts: HashMap<String, usize>,
+    len: usize,
+}
+
+impl HostCounts {
+    fn sync(&mut self, map: &HashMap<String, String>) {
+        if map.len() != self.len {
+            *self = Self::default();
+            for anonymous in map.values() {
+                self.count(anonymous);
+            }
+            self.len = map.len();
+        }
+    }
+
+    fn record(&mut self, anonymous: &str, map: &HashMap<String, String>) {
+        self.count(anonymous);
+        self.len = map.len();
+    }
+
+    fn count(&mut self, anonymous: &str) {
+        match anonymous.split_once('.') {
+            Some((_, fake_root)) if is_host_placeholder(anonymous) => {
+                *self.hosts.entry(fake_root.to_string()).or_default() += 1;
+            }
+            _ => {
+                self.roots.insert(anonymous.to_string());
+            }
+        }
+    }
+}
+
+// `host3.example.net`, as opposed to a fake root like `example.net`
+fn is_host_placeholder(anonymous: &str) -> bool {
+    anonymous
+        .split('.')
+        .next()
+        .and_then(|label| label.strip_prefix("host"))
+        .is_some_and(|number| !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_of() {
+        let owned = OwnedDomains::new(["acme.net", "*.corp.acme.net", ".Internal"]);
+
+        assert_eq!(
+            owned.root_of("db-prod-3.eu.corp.acme.net"),
+            Some("corp.acme.net")
+        );
+        assert_eq!(owned.root_of("ACME.net"), Some("acme.net"));
+        assert_eq!(owned.root_of("grafana.internal"), Some("internal"));
+        assert_eq!(owned.root_of("notacme.net"), None);
+        assert_eq!(owned.root_of("github.com"), None);
+    }
+
+    #[test]
+    fn test_detect_hostnames() {
+        let owned = OwnedDomains::new(["acme.net", "internal"]);
+        let text = "db-prod-3.eu.acme.net is down, see grafana.internal. github.com and notes.txt are fine";
+        let found: Vec<&str> = detect_hostnames(text, &owned)
+            .iter()
+            .map(|span| &text[span.start..span.end])
+            .collect();
+
+        assert_eq!(found, vec!["db-prod-3.eu.acme.net", "grafana.internal"]);
+    }
+
+    #[test]
+    fn test_owned_host_placeholder() {
+        let owned = OwnedDomains::new(["acme.net", "acme.com"]);
+        let mut map = HashMap::new();
+        let mut counts = HostCounts::default();
+        let mut anonymize =
+            |host: &str| owned_host_placeholder(host, &owned, &mut map, &mut counts);
+
+        assert_eq!(
+            anonymize("db-prod-3.eu.acme.net").as_deref(),
+            Some("host1.example.net")
+        );
+        assert_eq!(anonymize("acme.com").as_deref(), Some("example2.com"));
+        assert_eq!(
+            anonymize("db-prod-4.eu.acme.net").as_deref(),
+            Some("host2.example.net")
+        );
+        assert_eq!(
+            anonymize("DB-PROD-3.eu.acme.net").as_deref(),
+            Some("host1.example.net")
+        );
+        assert_eq!(
+            anonymize("www.acme.com").as_deref(),
+            Some("host1.example2.com")
+        );
+        assert_eq!(anonymize("github.com"), None);
+
+        // Counts are rebuilt for a map that grew elsewhere, e.g. a loaded one
+        let mut counts = HostCounts::default();
+        assert_eq!(
+            owned_host_placeholder("ci.acme.net", &owned, &mut map, &mut counts).as_deref(),
+            Some("host3.example.net")
+        );
+    }
+}
@@ -5,17 +5,44 @@ pub enum AnonymizationError {
     Pattern(PatternError),
     Io(std::io::Error),
     Legend(LegendError),
+    Export(ExportError),
+    Roster(RosterError),
+    Config(ConfigError),
 }
 
 #[derive(Debug)]
 pub enum PatternError {
     InvalidRegex(String),
     ProcessingFailed(String),
+    InvalidTemplate(String),
+    InvalidKey(String),
+    InvalidMask(String),
+}
+
+#[derive(Debug)]
+pub enum ExportError {
+    InvalidArchive(String),
+    InvalidJson(String),
+    UnsafePath(String),
+    UnsupportedFile(String),
+}
+
+#[derive(Debug)]
+pub enum RosterError {
+    InvalidJson(String),
+    InvalidCsv(String),
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    NotFound(String),
+    InvalidToml(String),
 }
 
 #[derive(Debug)]
 pub enum LegendError {
     FormatError(String),
+    ParseError(String),
 }
 
 impl fmt::Display for AnonymizationError {
@@ -24,6 +51,9 @@ impl fmt::Display for AnonymizationError {
             AnonymizationError::Pattern(err) => write!(f, "Pattern error: {}", err),
             AnonymizationError::Io(err) => write!(f, "IO error: {}", err),
             AnonymizationError::Legend(err) => write!(f, "Legend error: {}", err),
+            AnonymizationError::Export(err) => write!(f, "Export error: {}", err),
+            AnonymizationError::Roster(err) => write!(f, "Roster error: {}", err),
+            AnonymizationError::Config(err) => write!(f, "Config error: {}", err),
         }
     }
 }
@@ -33,6 +63,44 @@ impl fmt::Display for PatternError {
         match self {
             PatternError::InvalidRegex(msg) => write!(f, "Invalid regex: {}", msg),
             PatternError::ProcessingFailed(msg) => write!(f, "Processing failed: {}", msg),
+            PatternError::InvalidTemplate(msg) => write!(f, "Invalid template: {}", msg),
+            PatternError::InvalidKey(msg) => write!(f, "Invalid pseudonym key: {}", msg),
+            PatternError::InvalidMask(msg) => write!(f, "Invalid mask: {}", msg),
+        }
+    }
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::InvalidArchive(msg) => write!(f, "Invalid archive: {}", msg),
+            ExportError::InvalidJson(msg) => write!(f, "Invalid JSON: {}", msg),
+            ExportError::UnsafePath(path) => {
+                write!(f, "Unsafe path '{}': it leaves the export", path)
+            }
+            ExportError::UnsupportedFile(path) => write!(
+                f,
+                "Unsupported file '{}': only the JSON files of an export can be anonymized",
+                path
+            ),
+        }
+    }
+}
+
+impl fmt::Display for RosterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RosterError::InvalidJson(msg) => write!(f, "Invalid JSON: {}", msg),
+            RosterError::InvalidCsv(msg) => write!(f, "Invalid CSV: {}", msg),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::NotFound(path) => write!(f, "Config file not found: {}", path),
+            ConfigError::InvalidToml(msg) => write!(f, "Invalid TOML: {}", msg),
         }
     }
 }
@@ -41,12 +109,16 @@ impl fmt::Display for LegendError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             LegendError::FormatError(msg) => write!(f, "Format error: {}", msg),
+            LegendError::ParseError(msg) => write!(f, "Parse error: {}", msg),
         }
     }
 }
 
 impl std::error::Error for AnonymizationError {}
 impl std::error::Error for PatternError {}
+impl std::error::Error for ExportError {}
+impl std::error::Error for RosterError {}
+impl std::error::Error for ConfigError {}
 impl std::error::Error for LegendError {}
 
 impl From<PatternError> for AnonymizationError {
@@ -67,8 +139,32 @@ impl From<LegendError> for AnonymizationError {
     }
 }
 
+impl From<ExportError> for AnonymizationError {
+    fn from(err: ExportError) -> Self {
+        AnonymizationError::Export(err)
+    }
+}
+
+impl From<RosterError> for AnonymizationError {
+    fn from(err: RosterError) -> Self {
+        AnonymizationError::Roster(err)
+    }
+}
+
+impl From<ConfigError> for AnonymizationError {
+    fn from(err: ConfigError) -> Self {
+        AnonymizationError::Config(err)
+    }
+}
+
+impl From<zip::result::ZipError> for ExportError {
+    fn from(err: zip::result::ZipError) -> Self {
+        ExportError::InvalidArchive(err.to_string())
+    }
+}
+
 impl From<regex::Error> for PatternError {
     fn from(err: regex::Error) -> Self {
         PatternError::InvalidRegex(err.to_string())
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,728 @@
+use crate::anonymizer::Options;
+use crate::engine::{Category, Engine};
+use crate::error::{AnonymizationError, ExportError};
+use crate::legend::AnonymizationMap;
+use crate::patterns::{channel_keys, user_keys};
+use crate::roster::Roster;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Component, Path};
+use zip::ZipArchive;
+use zip::ZipWriter;
+use zip::write::SimpleFileOptions;
+
+// Root files describing the workspace, processed before any message
+// so that IDs and names are linked before they show up in messages
+const USERS_FILES: &[&str] = &["users.json", "org_users.json"];
+const CHANNELS_FILES: &[&str] = &["channels.json", "groups.json", "mpims.json", "dms.json"];
+
+// Fields holding Slack user IDs
+const USER_ID_KEYS: &[&str] = &["user", "user_id", "creator", "inviter", "parent_user_id"];
+// Fields holding lists of Slack user IDs
+const USER_ID_LIST_KEYS: &[&str] = &["users", "members", "reply_users"];
+// Fields holding Slack channel IDs
+const CHANNEL_ID_KEYS: &[&str] = &["channel", "channel_id"];
+// Fields holding a person's name
+const PERSON_NAME_KEYS: &[&str] = &[
+    "real_name",
+    "real_name_normalized",
+    "display_name",
+    "display_name_normalized",
+    "first_name",
+    "last_name",
+    "author_name",
+    "username",
+];
+// Free-text fields, anonymized like regular input text
+const TEXT_KEYS: &[&str] = &[
+    "text",
+    "title",
+    "value",
+    "fallback",
+    "pretext",
+    "preview",
+    "footer",
+    "phone",
+    "skype",
+    "url",
+    "url_private",
+    "url_private_download",
+    "permalink",
+    "permalink_public",
+    "from_url",
+    "title_link",
+];
+
+/// A single file of a Slack export, addressed by its path inside the export.
+pub struct ExportFile {
+    pub path: String,
+    pub contents: Vec<u8>,
+}
+
+/// Anonymizes a Slack workspace export (a directory or a `.zip` archive)
+/// into a structurally identical export at `output`.
+///
+/// The output is written as a zip archive when `output` ends with `.zip`,
+/// and as a directory otherwise.
+pub fn anonymize_export(
+    input: &Path,
+    output: &Path,
+    options: &Options,
+    map: &mut AnonymizationMap,
+) -> Result<(), AnonymizationError> {
+    let files = read_export(input)?;
+    let files = anonymize_export_files(files, options, map)?;
+    write_export(output, &files)
+}
+
+pub fn read_export(path: &Path) -> Result<Vec<ExportFile>, AnonymizationError> {
+    let mut files = Vec::new();
+
+    if path.is_dir() {
+        read_dir_files(path, path, &mut files)?;
+    } else {
+        let mut archive = ZipArchive::new(File::open(path)?).map_err(ExportError::from)?;
+        for index in 0..archive.len() {
+            let mut entry = archive.by_index(index).map_err(ExportError::from)?;
+            if entry.is_dir() {
+                continue;
+            }
+
+            // Entries like `../../.bashrc` must not escape the export
+            let Some(name) = entry.enclosed_name() else {
+                return Err(ExportError::UnsafePath(entry.name().to_string()).into());
+            };
+            let path = export_path(&name);
+            let mut contents = Vec::new();
+            entry.read_to_end(&mut contents)?;
+            files.push(ExportFile { path, contents });
+        }
+    }
+
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+fn read_dir_files(
+    root: &Path,
+    dir: &Path,
+    files: &mut Vec<ExportFile>,
+) -> Result<(), AnonymizationError> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            read_dir_files(root, &path, files)?;
+            continue;
+        }
+
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        files.push(ExportFile {
+            path: export_path(relative),
+            contents: fs::read(&path)?,
+        });
+    }
+
+    Ok(())
+}
+
+// Components joined with `/`, as in zip archives
+fn export_path(relative: &Path) -> String {
+    let components: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    components.join("/")
+}
+
+/// Fails on a path that is absolute or climbs out of the export with `..`,
+/// whether it came from an archive or from a renamed folder.
+fn check_path(path: &str) -> Result<(), ExportError> {
+    let relative = !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if relative {
+        Ok(())
+    } else {
+        Err(ExportError::UnsafePath(path.to_string()))
+    }
+}
+
+pub fn write_export(path: &Path, files: &[ExportFile]) -> Result<(), AnonymizationError> {
+    for file in files {
+        check_path(&file.path)?;
+    }
+
+    if path.extension().is_some_and(|ext| ext == "zip") {
+        let mut zip = ZipWriter::new(File::create(path)?);
+        for file in files {
+            zip.start_file(file.path.as_str(), SimpleFileOptions::default())
+                .map_err(ExportError::from)?;
+            zip.write_all(&file.contents)?;
+        }
+        zip.finish().map_err(ExportError::from)?;
+    } else {
+        for file in files {
+            let target = path.join(&file.path);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(target, &file.contents)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Anonymizes every JSON file of an export through one shared map.
+/// Any other file fails the export, since its content can't be checked.
+pub fn anonymize_export_files(
+    mut files: Vec<ExportFile>,
+    options: &Options,
+    map: &mut AnonymizationMap,
+) -> Result<Vec<ExportFile>, AnonymizationError> {
+    if let Some(file) = files.iter().find(|file| !file.path.ends_with(".json")) {
+        return Err(ExportError::UnsupportedFile(file.path.clone()).into());
+    }
+
+    // The export's own roster links IDs, handles, names and emails up front
+    for file in files
+        .iter()
+        .filter(|f| USERS_FILES.contains(&f.path.as_str()))
+    {
+        let json = String::from_utf8_lossy(&file.contents);
+        Roster::from_users_json(&json)?.seed(map, options);
+    }
+
+    let mut anonymizer = ExportAnonymizer {
+        options,
+        engine: Engine::new(options)?,
+        map,
+        folders: HashMap::new(),
+        folder_names: HashSet::new(),
+    };
+
+    // Workspace metadata first, then messages
+    let rank = |path: &str| {
+        if USERS_FILES.contains(&path) {
+            0
+        } else if CHANNELS_FILES.contains(&path) {
+            1
+        } else {
+            2
+        }
+    };
+    let mut order: Vec<usize> = (0..files.len()).collect();
+    order.sort_by_key(|&index| rank(&files[index].path));
+
+    for index in order {
+        let file = &mut files[index];
+        let mut value: Value = serde_json::from_slice(&file.contents)
+            .map_err(|e| ExportError::InvalidJson(format!("{}: {}", file.path, e)))?;
+
+        if USERS_FILES.contains(&file.path.as_str()) {
+            for_each_record(&mut value, |record| anonymizer.user_record(record))?;
+        } else if CHANNELS_FILES.contains(&file.path.as_str()) {
+            for_each_record(&mut value, |record| anonymizer.channel_record(record))?;
+        } else {
+            anonymizer.walk(&mut value)?;
+        }
+
+        file.path = anonymizer.file_path(&file.path)?;
+        file.contents = serde_json::to_vec_pretty(&value)
+            .map_err(|e| ExportError::InvalidJson(format!("{}: {}", file.path, e)))?;
+    }
+
+    Ok(files)
+}
+
+fn for_each_record(
+    value: &mut Value,
+    mut anonymize: impl FnMut(&mut Map<String, Value>) -> Result<(), AnonymizationError>,
+) -> Result<(), AnonymizationError> {
+    if let Value::Array(records) = value {
+        for record in records.iter_mut() {
+            if let Value::Object(record) = record {
+                anonymize(record)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+struct ExportAnonymizer<'a> {
+    options: &'a Options,
+    // Compiled once for the thousands of messages in an export
+    engine: Engine,
+    map: &'a mut AnonymizationMap,
+    // Channel folder of each channel name or DM ID, and the folders taken
+    folders: HashMap<String, String>,
+    folder_names: HashSet<String>,
+}
+
+impl ExportAnonymizer<'_> {
+    fn text(&mut self, text: &str) -> Result<String, AnonymizationError> {
+        Ok(self.engine.anonymize(text, self.options, self.map)?)
+    }
+
+    // A structured field, replaced like the same value found in text, so
+    // templates, pseudonyms, masks and the other modes apply to it too.
+    // Handles and channel names are replaced as their `@handle` and `#channel`
+    // mentions, and lose the sigil again
+    fn replace(
+        &mut self,
+        category: Category,
+        keys: &[String],
+        original: &str,
+    ) -> Result<Option<String>, AnonymizationError> {
+        let replacement =
+            self.engine
+                .replace_value(category, keys, original, self.options, self.map)?;
+        Ok(replacement.map(|anonymous| anonymous.trim_start_matches(['@', '#']).to_string()))
+    }
+
+    /// A users.json record or a message's `user_profile`.
+    fn user_record(&mut self, record: &mut Map<String, Value>) -> Result<(), AnonymizationError> {
+        let id = record.get("id").and_then(Value::as_str).map(str::to_string);
+        let handle = record
+            .get("name")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+
+        if id.is_some() || handle.is_some() {
+            let keys = user_keys(id.as_deref(), handle.as_deref());
+            for (key, original) in [("id", keys.first()), ("name", keys.last())] {
+                if let (Some(Value::String(value)), Some(original)) =
+                    (record.get_mut(key), original)
+                    && let Some(anonymous) = self.replace(Category::User, &keys, original)?
+                {
+                    *value = anonymous;
+                }
+            }
+        }
+
+        self.walk_fields(record, &["id", "name"])
+    }
+
+    /// A channels.json, groups.json, mpims.json or dms.json record.
+    fn channel_record(
+        &mut self,
+        record: &mut Map<String, Value>,
+    ) -> Result<(), AnonymizationError> {
+        let id = record.get("id").and_then(Value::as_str).map(str::to_string);
+        let name = record
+            .get("name")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+
+        if id.is_some() || name.is_some() {
+            let keys = channel_keys(id.as_deref(), name.as_deref());
+            if let Some(Value::String(id)) = record.get_mut("id")
+                && let Some(anonymous) = self.replace(Category::Channel, &keys, id)?
+            {
+                *id = anonymous;
+            }
+            // The name is also the channel's folder
+            if let Some(Value::String(name)) = record.get_mut("name") {
+                *name = self.folder(&keys, name)?;
+            }
+        }
+
+        self.walk_fields(record, &["id", "name"])
+    }
+
+    /// Channel folders are named after the channel (or its ID for DMs).
+    fn file_path(&mut self, path: &str) -> Result<String, AnonymizationError> {
+        match path.split_once('/') {
+            Some((folder, rest)) => {
+                let keys = if self.map.channels.contains_key(folder) {
+                    vec![folder.to_string()]
+                } else {
+                    channel_keys(None, Some(folder))
+                };
+                Ok(format!("{}/{}", self.folder(&keys, folder)?, rest))
+            }
+            None => Ok(path.to_string()),
+        }
+    }
+
+    // The folder of the channel `name`, whose last key is `#name` (or the ID of
+    // a DM). Channels whose replacements coincide, like `g***` for `general` and
+    // `growth` when masking, get numbered folders so their files stay apart
+    fn folder(&mut self, keys: &[String], name: &str) -> Result<String, AnonymizationError> {
+        if let Some(folder) = self.folders.get(name) {
+            return Ok(folder.clone());
+        }
+
+        let anonymous = self
+            .replace(
+                Category::Channel,
+                keys,
+                keys.last().map_or(name, String::as_str),
+            )?
+            .unwrap_or_else(|| name.to_string());
+        let mut folder = anonymous.clone();
+        let mut number = 1;
+        while self.folder_names.contains(&folder) {
+            number += 1;
+            folder = format!("{}-{}", anonymous, number);
+        }
+        self.folder_names.insert(folder.clone());
+        self.folders.insert(name.to_string(), folder.clone());
+        Ok(folder)
+    }
+
+    fn walk(&mut self, value: &mut Value) -> Result<(), AnonymizationError> {
+        match value {
+            Value::Array(items) => {
+                for item in items.iter_mut() {
+                    self.walk(item)?;
+                }
+            }
+            Value::Object(fields) => self.walk_fields(fields, &[])?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn walk_fields(
+        &mut self,
+        fields: &mut Map<String, Value>,
+        skip: &[&str],
+    ) -> Result<(), AnonymizationError> {
+        for (key, value) in fields.iter_mut() {
+            let key = key.as_str();
+            if skip.contains(&key) {
+                continue;
+            }
+
+            if key == "user_profile" {
+                if let Value::Object(profile) = value {
+                    self.user_record(profile)?;
+                }
+            } else if key == "files" {
+                if let Value::Array(files) = value {
+                    for file in files.iter_mut() {
+                        if let Value::Object(file) = file {
+                            if let Some(Value::String(name)) = file.get_mut("name") {
+                                *name = self.text(name)?;
+                            }
+                            self.walk_fields(file, &["name"])?;
+                        }
+                    }
+                }
+            } else if USER_ID_KEYS.contains(&key) {
+                self.map_string(value, Category::User)?;
+            } else if USER_ID_LIST_KEYS.contains(&key) {
+                if let Value::Array(ids) = value {
+                    for id in ids.iter_mut() {
+                        self.map_string(id, Category::User)?;
+                    }
+                }
+            } else if CHANNEL_ID_KEYS.contains(&key) {
+                self.map_string(value, Category::Channel)?;
+            } else if PERSON_NAME_KEYS.contains(&key) {
+                self.map_string(value, Category::DisplayName)?;
+            } else if key == "email" {
+                if let Value::String(email) = value {
+                    *email = self.text(email)?;
+                }
+            } else if TEXT_KEYS.contains(&key) {
+                if let Value::String(text) = value {
+                    *text = self.text(text)?;
+                } else {
+                    self.walk(value)?;
+                }
+            } else {
+                self.walk(value)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Replaces a non-empty string value as a value of `category`
+    fn map_string(
+        &mut self,
+        value: &mut Value,
+        category: Category,
+    ) -> Result<(), AnonymizationError> {
+        if let Value::String(original) = value
+            && !original.is_empty()
+            && let Some(anonymous) =
+                self.replace(category, std::slice::from_ref(original), original)?
+        {
+            *original = anonymous;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anonymizer::Detector;
+    use crate::masking::MaskRule;
+    use crate::persons::person_name;
+    use crate::pseudonyms::HASH_LEN;
+    use serde_json::json;
+
+    fn export_file(path: &str, value: Value) -> ExportFile {
+        ExportFile {
+            path: path.to_string(),
+            contents: serde_json::to_vec(&value).unwrap(),
+        }
+    }
+
+    fn sample_export() -> Vec<ExportFile> {
+        vec![
+            export_file(
+                "general/2024-01-15.json",
+                json!([{
+                    "type": "message",
+                    "user": "U024BE7LH",
+                    "text": "<@U0G9QF9C6> please email jon.snow@corp.com",
+                    "user_profile": {
+                        "real_name": "Aria Stark",
+                        "display_name": "aria",
+                        "name": "aria.stark"
+                    },
+                    "files": [{"id": "F1", "name": "ProjectX plan.pdf", "title": "Plan"}],
+                    "reactions": [{"name": "thumbsup", "users": ["U0G9QF9C6"], "count": 1}]
+                }]),
+            ),
+            export_file(
+                "channels.json",
+                json!([{
+                    "id": "C024BE7LR",
+                    "name": "general",
+                    "creator": "U024BE7LH",
+                    "members": ["U024BE7LH", "U0G9QF9C6"],
+                    "purpose": {"value": "Talk to @aria.stark", "creator": "U024BE7LH"}
+                }]),
+            ),
+            export_file(
+                "users.json",
+                json!([
+                    {
+                        "id": "U024BE7LH",
+                        "name": "aria.stark",
+                        "real_name": "Aria Stark",
+                        "profile": {"email": "aria@corp.com", "real_name": "Aria Stark"}
+                    },
+                    {"id": "U0G9QF9C6", "name": "jon.snow", "real_name": "Jon Snow"}
+                ]),
+            ),
+        ]
+    }
+
+    fn parse(files: &[ExportFile], path: &str) -> Value {
+        let file = files.iter().find(|f| f.path == path).unwrap();
+        serde_json::from_slice(&file.contents).unwrap()
+    }
+
+    #[test]
+    fn test_anonymize_export_files() {
+        let options = Options::new(false, vec!["ProjectX".to_string()]);
+        let mut map = AnonymizationMap::new();
+        let files = anonymize_export_files(sample_export(), &options, &mut map).unwrap();
+
+        let users = parse(&files, "users.json");
+        assert_eq!(users[0]["id"], "user1");
+        assert_eq!(users[0]["name"], "user1");
+        assert_eq!(users[0]["real_name"], "user1");
+        assert_eq!(users[0]["profile"]["email"], "user1@domain1.com");
+        assert_eq!(users[1]["id"], "user2");
+
+        let channels = parse(&files, "channels.json");
+        assert_eq!(channels[0]["id"], "ch1");
+        assert_eq!(channels[0]["name"], "ch1");
+        assert_eq!(channels[0]["members"], json!(["user1", "user2"]));
+        assert_eq!(channels[0]["purpose"]["value"], "Talk to @user1");
+
+        let messages = parse(&files, "ch1/2024-01-15.json");
+        let message = &messages[0];
+        assert_eq!(message["type"], "message");
+        assert_eq!(message["user"], "user1");
+        assert_eq!(message["text"], "@user2 please email user2@domain2.com");
+        assert_eq!(message["user_profile"]["display_name"], "name3");
+        assert_eq!(message["user_profile"]["name"], "user1");
+        assert_eq!(message["user_profile"]["real_name"], "user1");
+        assert_eq!(message["files"][0]["name"], "keyword1 plan.pdf");
+        assert_eq!(message["reactions"][0]["name"], "thumbsup");
+        assert_eq!(message["reactions"][0]["users"], json!(["user2"]));
+    }
+
+    #[test]
+    fn test_export_follows_detector_switches() {
+        let mut options = Options::default();
+        options.set_enabled(Detector::Channels, false);
+        let mut map = AnonymizationMap::new();
+        let files = anonymize_export_files(sample_export(), &options, &mut map).unwrap();
+
+        let channels = parse(&files, "channels.json");
+        assert_eq!(channels[0]["id"], "C024BE7LR");
+        assert_eq!(channels[0]["name"], "general");
+        assert_eq!(channels[0]["members"], json!(["user1", "user2"]));
+        assert!(files.iter().any(|f| f.path == "general/2024-01-15.json"));
+        assert!(map.channels.is_empty());
+    }
+
+    #[test]
+    fn test_export_modes() {
+        fn same_shape(anonymous: &str, original: &str) -> bool {
+            anonymous != original
+                && anonymous.len() == original.len()
+                && anonymous.find('.') == original.find('.')
+        }
+        let with = |set: fn(&mut Options)| {
+            let mut options = Options::default();
+            set(&mut options);
+            options
+        };
+        // Checks of the first user's ID, handle and real name, and of the channel name
+        type UserCheck = fn(&str, &str, &str) -> bool;
+        type ChannelCheck = fn(&str) -> bool;
+        let cases: [(&str, Options, UserCheck, ChannelCheck); 5] = [
+            (
+                "templates",
+                with(|o| {
+                    o.placeholders
+                        .insert(Category::User, "<PERSON_{n}>".to_string());
+                    o.placeholders
+                        .insert(Category::Channel, "[REDACTED]".to_string());
+                }),
+                |id, name, _| id == "<PERSON_1>" && name == id,
+                |channel| channel == "[REDACTED]",
+            ),
+            (
+                "realistic names",
+                with(|o| o.realistic_names = true),
+                |id, name, real_name| {
+                    id.contains('.') && name == id && real_name == person_name(id)
+                },
+                |channel| channel == "ch1",
+            ),
+            (
+                "pseudonyms",
+                with(|o| o.pseudonym_key = Some("team secret".to_string())),
+                |id, name, _| id.starts_with("user-") && id.len() == 5 + HASH_LEN && name == id,
+                |channel| channel.starts_with("ch-"),
+            ),
+            (
+                "masks",
+                with(|o| {
+                    for category in [Category::User, Category::DisplayName, Category::Channel] {
+                        o.masks.insert(category, MaskRule::default());
+                    }
+                }),
+                |id, name, real_name| {
+                    id == "U***" && name == "a***.s***" && real_name == "A*** S***"
+                },
+                |channel| channel == "g***",
+            ),
+            (
+                "preserved formats",
+                with(|o| {
+                    o.preserve_format =
+                        [Category::User, Category::DisplayName, Category::Channel].into()
+                }),
+                |id, name, real_name| {
+                    same_shape(id, "U024BE7LH")
+                        && same_shape(name, "aria.stark")
+                        && same_shape(real_name, "Aria Stark")
+                },
+                |channel| same_shape(channel, "general"),
+            ),
+        ];
+
+        for (mode, options, check_user, check_channel) in cases {
+            let run = || {
+                let mut files = sample_export();
+                files.push(export_file("growth/2024-01-16.json", json!([])));
+                anonymize_export_files(files, &options, &mut AnonymizationMap::new()).unwrap()
+            };
+            let files = run();
+
+            let users = parse(&files, "users.json");
+            let user = |field: &str| users[0][field].as_str().unwrap().to_string();
+            let id = user("id");
+            assert!(
+                check_user(&id, &user("name"), &user("real_name")),
+                "{}: {}",
+                mode,
+                users
+            );
+            let channels = parse(&files, "channels.json");
+            let channel = channels[0]["name"].as_str().unwrap();
+            assert!(check_channel(channel), "{}: {}", mode, channel);
+
+            let messages = parse(&files, &format!("{}/2024-01-15.json", channel));
+            assert_eq!(messages[0]["user"], id, "{}", mode);
+            // Channels sharing a placeholder keep apart folders
+            let growth = files.iter().find(|f| f.path.ends_with("/2024-01-16.json"));
+            assert_ne!(
+                growth.unwrap().path,
+                format!("{}/2024-01-16.json", channel),
+                "{}",
+                mode
+            );
+            // Stable without sharing a map
+            assert_eq!(parse(&run(), "users.json"), users, "{}", mode);
+        }
+    }
+
+    #[test]
+    fn test_export_zip_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("export.zip");
+        let output = dir.path().join("anonymized");
+        write_export(&input, &sample_export()).unwrap();
+
+        let mut map = AnonymizationMap::new();
+        anonymize_export(&input, &output, &Options::default(), &mut map).unwrap();
+
+        assert!(output.join("users.json").exists());
+        assert!(output.join("channels.json").exists());
+        assert!(output.join("ch1/2024-01-15.json").exists());
+        assert!(!output.join("general").exists());
+        assert_eq!(map.users.get("U024BE7LH"), Some(&"@user1".to_string()));
+    }
+
+    #[test]
+    fn test_export_paths_stay_inside() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("export.zip");
+        let mut zip = ZipWriter::new(File::create(&input).unwrap());
+        zip.start_file("../evil.json", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"[]").unwrap();
+        zip.finish().unwrap();
+
+        let err = read_export(&input).err().unwrap();
+        assert!(err.to_string().contains("../evil.json"));
+
+        for path in ["../evil.json", "/tmp/evil.json", "general/../../evil.json"] {
+            let output = dir.path().join("out");
+            let files = [export_file(path, json!([]))];
+            assert!(write_export(&output, &files).is_err());
+            assert!(write_export(&dir.path().join("out.zip"), &files).is_err());
+        }
+        assert!(!dir.path().join("evil.json").exists());
+    }
+
+    #[test]
+    fn test_export_rejects_other_files() {
+        let mut files = sample_export();
+        files.push(ExportFile {
+            path: "general/notes.txt".to_string(),
+            contents: b"Jon Snow's phone is +44 20 7946 0958".to_vec(),
+        });
+
+        let err = anonymize_export_files(files, &Options::default(), &mut AnonymizationMap::new())
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("general/notes.txt"));
+    }
+}
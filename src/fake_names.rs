@@ -0,0 +1,160 @@
+use crate::patterns::Taken;
+use crate::persons::link_person_with;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use std::collections::{HashMap, HashSet};
+
+// Gender-neutral first names
+const FIRST_NAMES: &[&str] = &[
+    "alex", "ari", "avery", "bailey", "blake", "cameron", "casey", "charlie", "dakota", "drew",
+    "eden", "elliot", "emerson", "finley", "frankie", "harper", "hayden", "jamie", "jesse",
+    "jordan", "jules", "kai", "kendall", "logan", "marley", "noel", "oakley", "parker", "peyton",
+    "quinn", "reese", "riley", "river", "robin", "rowan", "sage", "sam", "skyler", "taylor",
+    "tatum",
+];
+
+const LAST_NAMES: &[&str] = &[
+    "adams", "bennett", "brooks", "carter", "collins", "cooper", "ellis", "fisher", "foster",
+    "gray", "hayes", "hughes", "jensen", "keller", "marsh", "mills", "morgan", "nash", "novak",
+    "parks", "perry", "price", "reed", "ross", "russell", "sawyer", "shaw", "sutton", "tate",
+    "turner", "vance", "walsh", "ward", "webb", "west", "wilde", "young", "lowe", "hart", "page",
+];
+
+// Draws before giving up on a realistic name and falling back to `personN`
+const MAX_DRAWS: usize = 256;
+
+/// Picks plausible fake people (`riley.morgan`) for the realistic mode.
+///
+/// The choice only depends on the seed and the number of people already
+/// named, so the same input, map and seed always produce the same names.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FakeNames {
+    seed: u64,
+}
+
+impl FakeNames {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    /// A fake person as `first.last` for the next of `count` people, or `None`
+    /// when every draw was taken.
+    ///
+    /// Names in `taken` are in use already. Neither part of the fake name may be
+    /// a real word, so a fake never collides with a real name.
+    pub fn pick(
+        &self,
+        count: usize,
+        taken: &HashSet<String>,
+        is_real: impl Fn(&str) -> bool,
+    ) -> Option<String> {
+        let mut rng = StdRng::seed_from_u64(self.seed.wrapping_add(count as u64));
+
+        (0..MAX_DRAWS).find_map(|_| {
+            let first = FIRST_NAMES.choose(&mut rng)?;
+            let last = LAST_NAMES.choose(&mut rng)?;
+            let person = format!("{}.{}", first, last);
+
+            let clashes = is_real(first) || is_real(last);
+            (!clashes && !taken.contains(&person)).then_some(person)
+        })
+    }
+}
+
+/// Words of the real names linked to people so far, kept next to the persons
+/// map like [`Taken`] so that naming a new person doesn't split every name again.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct KnownWords {
+    words: HashSet<String>,
+    len: usize,
+    // Words of real names not linked to anyone yet, e.g. the rest of a roster
+    reserved: HashSet<String>,
+}
+
+impl KnownWords {
+    /// Keeps fake names away from the words of `texts` too.
+    pub(crate) fn reserve<'a>(&mut self, texts: impl IntoIterator<Item = &'a str>) {
+        self.reserved.extend(words(texts));
+    }
+}
+
+/// Links `originals` to one person like [`link_person`](crate::persons::link_person),
+/// naming a new person with a fake name that appears nowhere in `text`, the
+/// originals or the people known so far.
+pub(crate) fn link_fake_person(
+    originals: &[&str],
+    persons: &mut HashMap<String, String>,
+    taken: &mut Taken,
+    known: &mut KnownWords,
+    fake_names: &FakeNames,
+    text: &str,
+) -> String {
+    if persons.len() != known.len {
+        known.words = words(persons.keys().map(String::as_str));
+    }
+
+    let real_words = words(originals.iter().copied().chain([text]));
+    let person = link_person_with(originals, persons, taken, |people| {
+        let is_real = |word: &str| {
+            real_words.contains(word) || known.words.contains(word) || known.reserved.contains(word)
+        };
+        fake_names
+            .pick(people.len(), people, is_real)
+            .unwrap_or_else(|| format!("person{}", people.len() + 1))
+    });
+
+    known.words.extend(words(originals.iter().copied()));
+    known.len = persons.len();
+    person
+}
+
+/// Lowercase words of `text`, e.g. the input and the names already known.
+pub(crate) fn words<'a>(texts: impl IntoIterator<Item = &'a str>) -> HashSet<String> {
+    texts
+        .into_iter()
+        .flat_map(|text| text.split(|c: char| !c.is_alphanumeric()))
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_is_reproducible() {
+        let names = FakeNames::new(7);
+        let taken = HashSet::new();
+
+        assert_eq!(
+            names.pick(0, &taken, |_| false),
+            FakeNames::new(7).pick(0, &taken, |_| false)
+        );
+        assert_ne!(
+            names.pick(0, &taken, |_| false),
+            names.pick(1, &taken, |_| false)
+        );
+    }
+
+    #[test]
+    fn test_pick_avoids_real_and_taken_names() {
+        let names = FakeNames::new(1);
+        let no_taken = HashSet::new();
+        let first = names.pick(0, &no_taken, |_| false).unwrap();
+        let (first_name, last_name) = first.split_once('.').unwrap();
+
+        let real = words([format!("{} Snow", first_name).as_str()]);
+        let other = names
+            .pick(0, &no_taken, |word| real.contains(word))
+            .unwrap();
+        assert!(!other.starts_with(&format!("{}.", first_name)));
+
+        let taken = HashSet::from([first.clone()]);
+        assert_ne!(names.pick(0, &taken, |_| false).unwrap(), first);
+
+        let real = words(FIRST_NAMES.iter().copied().chain([last_name]));
+        assert_eq!(names.pick(0, &no_taken, |word| real.contains(word)), None);
+    }
+}
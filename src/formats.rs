@@ -0,0 +1,176 @@
+use crate::engine::Category;
+use crate::patterns::Taken;
+use crate::pseudonyms::PseudonymKey;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+// Draws before settling for a replacement equal to the original or to another value's
+const MAX_ATTEMPTS: u32 = 16;
+
+/// Draws replacements with the same length and character classes as the
+/// original, for logs and tables where columns must stay aligned.
+///
+/// A replacement only depends on the value and the seed, or the pseudonym key
+/// when one is set, so a value is replaced alike in every run.
+#[derive(Debug, Clone, Default)]
+pub struct FormatPreserver {
+    seed: u64,
+    key: Option<PseudonymKey>,
+}
+
+impl FormatPreserver {
+    pub fn new(seed: u64, key: Option<PseudonymKey>) -> Self {
+        Self { seed, key }
+    }
+
+    /// `value` with letters replaced by letters of the same case and digits by
+    /// digits; separators, sigils and Slack markup stay. The scheme of a URL
+    /// stays too, and the letters of a commit SHA remain hex digits.
+    ///
+    /// Each `attempt` draws another replacement.
+    pub fn replace(&self, category: Category, value: &str, attempt: u32) -> String {
+        let mut rng = StdRng::from_seed(self.digest(value, attempt));
+        let kept = match category {
+            Category::Url => value.find("://").map_or(0, |pos| pos + 3),
+            _ => 0,
+        };
+        let letters = match category {
+            Category::Commit => 6,
+            _ => 26,
+        };
+
+        value
+            .char_indices()
+            .map(|(i, c)| match c {
+                _ if i < kept => c,
+                c if c.is_ascii_digit() => char::from(b'0' + rng.gen_range(0..10)),
+                c if c.is_uppercase() => char::from(b'A' + rng.gen_range(0..letters)),
+                c if c.is_alphabetic() => char::from(b'a' + rng.gen_range(0..letters)),
+                c => c,
+            })
+            .collect()
+    }
+
+    // Case is ignored, so `@Jon` and `@jon` only differ in case
+    fn digest(&self, value: &str, attempt: u32) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        match &self.key {
+            Some(key) => hasher.update(key.hash(value)),
+            None => {
+                hasher.update(self.seed.to_le_bytes());
+                hasher.update(value.to_lowercase());
+            }
+        }
+        hasher.update(attempt.to_le_bytes());
+        hasher.finalize().into()
+    }
+}
+
+/// Replaces `original` with its recorded replacement, or draws one that differs
+/// from the original and from the replacements of other values.
+pub(crate) fn format_placeholder(
+    preserver: &FormatPreserver,
+    category: Category,
+    original: &str,
+    formats: &mut HashMap<String, String>,
+    taken: &mut Taken,
+) -> String {
+    if let Some(anonymous) = formats.get(original) {
+        return anonymous.clone();
+    }
+
+    let taken_values = taken.values(formats);
+    let anonymous = draw(preserver, category, original, |anonymous| {
+        !taken_values.contains(anonymous)
+    });
+    taken.insert(formats, original, &anonymous);
+    anonymous
+}
+
+/// Like [`format_placeholder`], but never recorded, for secrets.
+pub(crate) fn format_secret(preserver: &FormatPreserver, secret: &str) -> String {
+    draw(preserver, Category::Secret, secret, |_| true)
+}
+
+fn draw(
+    preserver: &FormatPreserver,
+    category: Category,
+    original: &str,
+    available: impl Fn(&String) -> bool,
+) -> String {
+    (0..MAX_ATTEMPTS)
+        .map(|attempt| preserver.replace(category, original, attempt))
+        .find(|anonymous| anonymous != original && available(anonymous))
+        .unwrap_or_else(|| preserver.replace(category, original, 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classes(value: &str) -> String {
+        value
+            .chars()
+            .map(|c| match c {
+                c if c.is_ascii_digit() => '9',
+                c if c.is_uppercase() => 'X',
+                c if c.is_alphabetic() => 'x',
+                c => c,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_replace_preserves_format() {
+        let preserver = FormatPreserver::default();
+        let values = [
+            (Category::DisplayName, "Jon Snow"),
+            (Category::Email, "jon.snow@acme.com"),
+            (Category::Phone, "+44 20 7946 0958"),
+            (Category::Ip, "10.0.12.34"),
+            (Category::User, "<@U024BE7LH|jon>"),
+            (Category::DisplayName, "Ярослав"),
+        ];
+
+        for (category, value) in values {
+            let anonymous = preserver.replace(category, value, 0);
+            assert_ne!(anonymous, value);
+            assert_eq!(classes(&anonymous), classes(value));
+            assert_eq!(
+                anonymous,
+                FormatPreserver::default().replace(category, value, 0)
+            );
+        }
+
+        let url = preserver.replace(Category::Url, "https://acme.com/docs", 0);
+        assert!(url.starts_with("https://") && url.len() == 21);
+        let sha = preserver.replace(Category::Commit, "a1b2c3d", 0);
+        assert!(sha.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(
+            FormatPreserver::new(7, None).replace(Category::DisplayName, "Jon Snow", 0),
+            preserver.replace(Category::DisplayName, "Jon Snow", 0)
+        );
+    }
+
+    #[test]
+    fn test_format_placeholder_is_recorded() {
+        let preserver = FormatPreserver::default();
+        let (mut formats, mut taken) = (HashMap::new(), Taken::default());
+        let mut placeholder = |original| {
+            format_placeholder(
+                &preserver,
+                Category::User,
+                original,
+                &mut formats,
+                &mut taken,
+            )
+        };
+
+        let jon = placeholder("@jon");
+        assert_eq!(placeholder("@JON"), jon.to_uppercase());
+        assert_eq!(placeholder("@jon"), jon);
+        assert_eq!(formats.len(), 2);
+    }
+}
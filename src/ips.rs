@@ -0,0 +1,588 @@
+use crate::engine::{Category, Span};
+use crate::error::PatternError;
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::LazyLock;
+
+// Candidates only, `parse_ip` decides what is an address
+const IPV4_PATTERN: &str = r"\b(?:\d{1,3}\.){3}\d{1,3}(?:/\d{1,2})?";
+const IPV6_PATTERN: &str = r"[0-9A-Fa-f]{0,4}(?::[0-9A-Fa-f]{0,4}){2,7}(?:/\d{1,3})?";
+
+static IPV4_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(IPV4_PATTERN).unwrap());
+static IPV6_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(IPV6_PATTERN).unwrap());
+
+/// Finds IPv4 and IPv6 addresses and CIDR ranges (`10.0.5.17`, `10.0.5.0/24`,
+/// `fe80::1`, `2001:abcd:12::/48`).
+///
+/// Loopback, unspecified and broadcast addresses identify nobody and are kept,
+/// as are documentation ranges, which is what the placeholders use.
+pub fn detect_ips(text: &str) -> Vec<Span> {
+    IPV4_RE
+        .find_iter(text)
+        .chain(IPV6_RE.find_iter(text))
+        .filter(|m| is_standalone(text, m.start(), m.end()))
+        .filter(|m| parse_ip(m.as_str()).is_some_and(|(addr, _)| !is_reserved(addr)))
+        .map(|m| Span::new(m.range(), Category::Ip, vec![m.as_str().to_string()]))
+        .collect()
+}
+
+// Not part of a longer token: `v1.2.3.4`, `std::fs`, `1.2.3.4.5`
+fn is_standalone(text: &str, start: usize, end: usize) -> bool {
+    let before = text[..start].chars().next_back();
+    if before.is_some_and(|c| c.is_alphanumeric() || "_.:".contains(c)) {
+        return false;
+    }
+
+    let mut after = text[end..].chars();
+    match (after.next(), after.next()) {
+        (Some(c), _) if c.is_alphanumeric() || c == '_' || c == ':' => false,
+        (Some('.'), Some(next)) if next.is_ascii_digit() => false,
+        _ => true,
+    }
+}
+
+/// Parses `address` or `address/prefix`.
+pub fn parse_ip(text: &str) -> Option<(IpAddr, Option<u8>)> {
+    let (address, prefix) = match text.split_once('/') {
+        Some((address, prefix)) => (address, Some(prefix.parse::<u8>().ok()?)),
+        None => (text, None),
+    };
+    let address: IpAddr = address.parse().ok()?;
+
+    let max_prefix = if address.is_ipv4() { 32 } else { 128 };
+    if prefix.is_some_and(|prefix| prefix > max_prefix) {
+        return None;
+    }
+    Some((address, prefix))
+}
+
+fn is_reserved(address: IpAddr) -> bool {
+    match address {
+        IpAddr::V4(v4) => {
+            let [a, b, c, _] = v4.octets();
+            v4.is_loopback()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || matches!((a, b, c), (192, 0, 2) | (198, 51, 100) | (203, 0, 113))
+                || (a == 198 && (b == 18 || b == 19))
+                || a >= 240
+        }
+        IpAddr::V6(v6) => {
+            let segments = v6.segments();
+            v6.is_loopback()
+                || v6.is_unspecified()
+                || segments[..2] == [0x2001, 0xdb8]
+                || segments[0] == 0x3fff && segments[1] < 0x1000
+        }
+    }
+}
+
+/// Maps an address or CIDR range into the documentation ranges, keeping the
+/// prefix length.
+///
+/// A network is mapped at its own prefix length, and an address without one
+/// per /24 (IPv4) or /64 (IPv6). What is inside a known network gets a fake
+/// inside its fake, so `10.0.0.0/16` and `10.0.5.17` stay nested. IPv4 hosts
+/// keep their host octet, IPv6 hosts are numbered `::1`, `::2`, ... Fakes come
+/// from 192.0.2.0/24, 198.51.100.0/24, 203.0.113.0/24, the 198.18.0.0/15
+/// benchmarking range and the reserved 240.0.0.0/4, or 2001:db8::/32 and
+/// 3fff::/20 for IPv6, and running out of them is an error.
+///
+/// A network seen after addresses in it that already got unrelated fakes gets
+/// a new fake of its own.
+pub(crate) fn ip_placeholder(
+    original: &str,
+    map: &mut HashMap<String, String>,
+    networks: &mut IpNetworks,
+) -> Result<String, PatternError> {
+    if let Some(anonymous) = map.get(original) {
+        return Ok(anonymous.clone());
+    }
+    let Some((address, prefix)) = parse_ip(original) else {
+        return Ok(original.to_string());
+    };
+
+    networks.sync(map);
+    let fake = match address {
+        IpAddr::V4(v4) => networks.fake_ipv4(v4, prefix).map(IpAddr::V4),
+        IpAddr::V6(v6) => networks.fake_ipv6(v6, prefix).map(IpAddr::V6),
+    };
+    let Some(fake) = fake else {
+        return Err(PatternError::ProcessingFailed(format!(
+            "no documentation range left for {}",
+            original
+        )));
+    };
+    let anonymous = match prefix {
+        Some(prefix) => format!("{}/{}", fake, prefix),
+        None => fake.to_string(),
+    };
+
+    map.insert(original.to_string(), anonymous.clone());
+    networks.len = map.len();
+    Ok(anonymous)
+}
+
+/// The order to map addresses found together in: wider networks first, so
+/// the addresses in them land in their fakes.
+pub(crate) fn network_order(original: &str) -> u8 {
+    parse_ip(original)
+        .and_then(|(_, prefix)| prefix)
+        .unwrap_or(u8::MAX)
+}
+
+const V4_POOL: &[(u128, u128)] = &[
+    (0xc000_0200, 0xc000_0300),
+    (0xc633_6400, 0xc633_6500),
+    (0xcb00_7100, 0xcb00_7200),
+    (0xc612_0000, 0xc614_0000),
+    (0xf000_0000, 0x1_0000_0000),
+];
+
+const V6_POOL: &[(u128, u128)] = &[
+    (
+        0x2001_0db8_0000_0001_0000_0000_0000_0000,
+        0x2001_0db9_0000_0000_0000_0000_0000_0000,
+    ),
+    (
+        0x3fff_0000_0000_0000_0000_0000_0000_0000,
+        0x3fff_1000_0000_0000_0000_0000_0000_0000,
+    ),
+];
+
+/// The networks the fake addresses of a map already use, kept next to it like
+/// [`Taken`](crate::patterns::Taken) so a new address doesn't re-parse the map.
+#[derive(Debug, Clone)]
+pub(crate) struct IpNetworks {
+    len: usize,
+    v4: Networks,
+    v6: Networks,
+    v6_addresses: HashMap<u128, u128>,
+    // Hosts numbered so far in each fake /64
+    v6_hosts: HashMap<u128, u128>,
+}
+
+impl Default for IpNetworks {
+    fn default() -> Self {
+        Self {
+            len: 0,
+            v4: Networks::new(32, V4_POOL),
+            v6: Networks::new(128, V6_POOL),
+            v6_addresses: HashMap::new(),
+            v6_hosts: HashMap::new(),
+        }
+    }
+}
+
+impl IpNetworks {
+    fn sync(&mut self, map: &HashMap<String, String>) {
+        if map.len() != self.len {
+            *self = Self::default();
+            // Wider networks first, so the ones inside find them
+            let mut entries: Vec<(&String, &String)> = map.iter().collect();
+            entries.sort_by_key(|(original, _)| network_order(original));
+            for (original, anonymous) in entries {
+                self.record(original, anonymous);
+            }
+            self.len = map.len();
+        }
+    }
+
+    fn record(&mut self, original: &str, anonymous: &str) {
+        let (Some((original, prefix)), Some((anonymous, _))) =
+            (parse_ip(original), parse_ip(anonymous))
+        else {
+            return;
+        };
+        match (original, anonymous) {
+            (IpAddr::V4(original), IpAddr::V4(fake)) => {
+                let (original, fake) = (u32::from(original) as u128, u32::from(fake) as u128);
+                if let Some(prefix) = prefix.filter(|prefix| *prefix < 24) {
+                    self.v4.record(prefix, original, fake);
+                    if self.v4.is_network(prefix, original) {
+                        return;
+                    }
+                }
+                self.v4.record(24, original, fake);
+            }
+            (IpAddr::V6(original), IpAddr::V6(fake)) => {
+                let (original, fake) = (u128::from(original), u128::from(fake));
+                if let Some(prefix) = prefix.filter(|prefix| *prefix < 64) {
+                    self.v6.record(prefix, original, fake);
+                    if self.v6.is_network(prefix, original) {
+                        return;
+                    }
+                }
+                self.v6.record(64, original, fake);
+                self.v6_addresses.entry(original).or_insert(fake);
+                let host = fake & !self.v6.mask(64);
+                let hosts = self.v6_hosts.entry(fake & self.v6.mask(64)).or_default();
+                *hosts = (*hosts).max(host);
+            }
+            _ => {}
+        }
+    }
+
+    fn fake_ipv4(&mut self, address: Ipv4Addr, prefix: Option<u8>) -> Option<Ipv4Addr> {
+        let address = u32::from(address) as u128;
+        if let Some(prefix) = prefix.filter(|prefix| *prefix < 24) {
+            let network = self.v4.fake(prefix, address)?;
+            if self.v4.is_network(prefix, address) {
+                return Some(Ipv4Addr::from(network as u32));
+            }
+        }
+        let network = self.v4.fake(24, address)?;
+        Some(Ipv4Addr::from((network | (address & 0xff)) as u32))
+    }
+
+    fn fake_ipv6(&mut self, address: Ipv6Addr, prefix: Option<u8>) -> Option<Ipv6Addr> {
+        let address = u128::from(address);
+
+        // The same address written differently (`fe80::1`, `fe80:0:0:0:0:0:0:1`)
+        if let Some(fake) = self.v6_addresses.get(&address) {
+            return Some(Ipv6Addr::from(*fake));
+        }
+        if let Some(prefix) = prefix.filter(|prefix| *prefix < 64) {
+            let network = self.v6.fake(prefix, address)?;
+            if self.v6.is_network(prefix, address) {
+                return Some(Ipv6Addr::from(network));
+            }
+        }
+        let network = self.v6.fake(64, address)?;
+
+        // A network address stays a network address
+        let host = if address & !self.v6.mask(64) == 0 {
+            0
+        } else {
+            let hosts = self.v6_hosts.entry(network).or_default();
+            *hosts += 1;
+            *hosts
+        };
+        self.v6_addresses.insert(address, network | host);
+        Some(Ipv6Addr::from(network | host))
+    }
+}
+
+/// Real networks of one address family and their fakes, nested like them.
+#[derive(Debug, Clone)]
+struct Networks {
+    bits: u32,
+    // Where the fakes of networks inside no known network go, in order
+    pool: &'static [(u128, u128)],
+    known: HashMap<(u8, u128), Network>,
+    top: Network,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Network {
+    fake: u128,
+    // Outermost known networks inside this one, by real start → prefix
+    inner: BTreeMap<u128, u8>,
+    // Fakes taken in this network's fake, by fake start → (prefix, real start)
+    taken: BTreeMap<u128, (u8, u128)>,
+    // Where to look for the next free fake of each prefix: region and start
+    next: HashMap<u8, (usize, u128)>,
+}
+
+impl Networks {
+    fn new(bits: u32, pool: &'static [(u128, u128)]) -> Self {
+        Self {
+            bits,
+            pool,
+            known: HashMap::new(),
+            top: Network::default(),
+        }
+    }
+
+    fn mask(&self, prefix: u8) -> u128 {
+        let all = u128::MAX >> (128 - self.bits);
+        all & !(all >> prefix)
+    }
+
+    fn size(&self, prefix: u8) -> Option<u128> {
+        1u128.checked_shl(self.bits - u32::from(prefix))
+    }
+
+    fn is_network(&self, prefix: u8, address: u128) -> bool {
+        address & !self.mask(prefix) == 0
+    }
+
+    /// The fake start of the network `prefix` bits of `address` are in.
+    fn fake(&mut self, prefix: u8, address: u128) -> Option<u128> {
+        let start = address & self.mask(prefix);
+        if let Some(network) = self.known.get(&(prefix, start)) {
+            return Some(network.fake);
+        }
+        let parent = self.parent(prefix, start);
+        let fake = match self.nesting_fake(parent, prefix, start) {
+            Some(fake) => fake,
+            None => self.free(parent, prefix)?,
+        };
+        self.insert(parent, prefix, start, fake);
+        Some(fake)
+    }
+
+    fn record(&mut self, prefix: u8, original: u128, fake: u128) {
+        let (start, fake) = (original & self.mask(prefix), fake & self.mask(prefix));
+        if !self.known.contains_key(&(prefix, start)) {
+            let parent = self.parent(prefix, start);
+            self.insert(parent, prefix, start, fake);
+        }
+    }
+
+    // The innermost known network around `start/prefix`
+    fn parent(&self, prefix: u8, start: u128) -> Option<(u8, u128)> {
+        (0..prefix)
+            .rev()
+            .map(|prefix| (prefix, start & self.mask(prefix)))
+            .find(|key| self.known.contains_key(key))
+    }
+
+    fn network(&self, key: Option<(u8, u128)>) -> &Network {
+        key.map_or(&self.top, |key| &self.known[&key])
+    }
+
+    fn network_mut(&mut self, key: Option<(u8, u128)>) -> &mut Network {
+        match key {
+            Some(key) => self.known.get_mut(&key).unwrap(),
+            None => &mut self.top,
+        }
+    }
+
+    // Where fakes inside `key` may go
+    fn regions(&self, key: Option<(u8, u128)>) -> Vec<(u128, u128)> {
+        match key {
+            Some((prefix, start)) => {
+                let fake = self.known[&(prefix, start)].fake;
+                vec![(fake, fake + self.size(prefix).unwrap_or(0))]
+            }
+            None => self.pool.to_vec(),
+        }
+    }
+
+    // The real networks of `parent` inside `start/prefix`
+    fn inside(&self, parent: Option<(u8, u128)>, prefix: u8, start: u128) -> Vec<(u8, u128)> {
+        let last = start | !self.mask(prefix) & (u128::MAX >> (128 - self.bits));
+        self.network(parent)
+            .inner
+            .range(start..=last)
+            .map(|(start, prefix)| (*prefix, *start))
+            .collect()
+    }
+
+    // A fake around the fakes of the known networks inside `start/prefix`, when
+    // they share one that nothing else of `parent` uses
+    fn nesting_fake(&self, parent: Option<(u8, u128)>, prefix: u8, start: u128) -> Option<u128> {
+        let inside = self.inside(parent, prefix, start);
+        let fakes: HashSet<u128> = inside.iter().map(|key| self.known[key].fake).collect();
+        let fake = *fakes.iter().next()? & self.mask(prefix);
+        if fakes.iter().any(|inner| inner & self.mask(prefix) != fake) {
+            return None;
+        }
+
+        let end = fake + self.size(prefix)?;
+        if !self
+            .regions(parent)
+            .iter()
+            .any(|(from, to)| *from <= fake && end <= *to)
+        {
+            return None;
+        }
+        let overlapping = self
+            .network(parent)
+            .taken
+            .range(..end)
+            .rev()
+            .take_while(|(taken, (prefix, _))| *taken + self.size(*prefix).unwrap_or(0) > fake);
+        let mut count = 0;
+        for (taken, _) in overlapping {
+            if !fakes.contains(taken) {
+                return None;
+            }
+            count += 1;
+        }
+        (count == inside.len()).then_some(fake)
+    }
+
+    // The first free fake of `prefix` in `parent`
+    fn free(&mut self, parent: Option<(u8, u128)>, prefix: u8) -> Option<u128> {
+        let size = self.size(prefix)?;
+        let regions = self.regions(parent);
+        let network = self.network(parent);
+        let (first, mut slot) = network.next.get(&prefix).copied().unwrap_or_default();
+        for (index, (from, to)) in regions.into_iter().enumerate().skip(first) {
+            if index > first {
+                slot = 0;
+            }
+            slot = slot.max(from).div_ceil(size) * size;
+            while slot + size <= to {
+                match network.taken.range(..slot + size).next_back() {
+                    Some((taken, (prefix, _))) if taken + self.size(*prefix)? > slot => {
+                        slot = (taken + self.size(*prefix)?).div_ceil(size) * size;
+                    }
+                    _ => {
+                        self.network_mut(parent)
+                            .next
+                            .insert(prefix, (index, slot + size));
+                        return Some(slot);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn insert(&mut self, parent: Option<(u8, u128)>, prefix: u8, start: u128, fake: u128) {
+        let size = self.size(prefix).unwrap_or(0);
+        let inside = self.inside(parent, prefix, start);
+        let mut network = Network {
+            fake,
+            ..Network::default()
+        };
+
+        // The networks inside move in, and so do their fakes when they nest
+        let outer = self.network_mut(parent);
+        for &(inner_prefix, inner_start) in &inside {
+            outer.inner.remove(&inner_start);
+            network.inner.insert(inner_start, inner_prefix);
+        }
+        for &key in &inside {
+            let inner_fake = self.known[&key].fake;
+            let outer = self.network_mut(parent);
+            if (fake..fake + size).contains(&inner_fake)
+                && outer.taken.get(&inner_fake) == Some(&key)
+            {
+                outer.taken.remove(&inner_fake);
+                network.taken.insert(inner_fake, key);
+            }
+        }
+
+        let fits = self
+            .regions(parent)
+            .iter()
+            .any(|(from, to)| *from <= fake && fake < *to);
+        let outer = self.network_mut(parent);
+        outer.inner.insert(start, prefix);
+        if fits {
+            outer.taken.insert(fake, (prefix, start));
+        } else {
+            self.top.taken.insert(fake, (prefix, start));
+        }
+        self.known.insert((prefix, start), network);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detected(text: &str) -> Vec<&str> {
+        let mut spans = detect_ips(text);
+        spans.sort_by_key(|span| span.start);
+        spans
+            .iter()
+            .map(|span| &text[span.start..span.end])
+            .collect()
+    }
+
+    #[test]
+    fn test_detect_ips() {
+        let text = "db at 10.0.5.17 (10.0.5.0/24), gw fe80::1, v6 2001:abcd:12:3::/64 and [2a00:1450:4001:81c::200e]:443";
+
+        assert_eq!(
+            detected(text),
+            vec![
+                "10.0.5.17",
+                "10.0.5.0/24",
+                "fe80::1",
+                "2001:abcd:12:3::/64",
+                "2a00:1450:4001:81c::200e"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ip_lookalikes_are_kept() {
+        let text = "v1.2.3.4, 1.2.3.4.5, 999.1.1.1, std::fs::read, 10:30:00, \
+                    00:1a:2b:3c:4d:5e, 127.0.0.1, ::1 and 192.0.2.7";
+
+        assert!(detected(text).is_empty());
+    }
+
+    #[test]
+    fn test_ipv4_keeps_subnets() {
+        let mut map = HashMap::new();
+        let mut networks = IpNetworks::default();
+        let mut ip_placeholder = |ip: &str| ip_placeholder(ip, &mut map, &mut networks).unwrap();
+
+        assert_eq!(ip_placeholder("10.0.5.17"), "192.0.2.17");
+        assert_eq!(ip_placeholder("172.16.9.4"), "198.51.100.4");
+        assert_eq!(ip_placeholder("10.0.5.200"), "192.0.2.200");
+        assert_eq!(ip_placeholder("10.0.5.0/24"), "192.0.2.0/24");
+        assert_eq!(ip_placeholder("10.0.6.1"), "203.0.113.1");
+        assert_eq!(ip_placeholder("10.0.7.1"), "198.18.0.1");
+    }
+
+    #[test]
+    fn test_networks_keep_their_prefix() {
+        let mut map = HashMap::new();
+        let mut networks = IpNetworks::default();
+        let mut ip_placeholder = |ip: &str| ip_placeholder(ip, &mut map, &mut networks).unwrap();
+
+        assert_eq!(ip_placeholder("10.0.0.0/8"), "240.0.0.0/8");
+        assert_eq!(ip_placeholder("10.1.2.0/24"), "240.0.0.0/24");
+        assert_eq!(ip_placeholder("10.1.0.0/16"), "240.0.0.0/16");
+        assert_eq!(ip_placeholder("10.1.3.4"), "240.0.1.4");
+        assert_eq!(ip_placeholder("10.2.0.1"), "240.1.0.1");
+        assert_eq!(ip_placeholder("10.1.2.9/16"), "240.0.0.9/16");
+        assert_eq!(ip_placeholder("172.16.0.0/12"), "241.0.0.0/12");
+        assert_eq!(ip_placeholder("2a00:1450::/32"), "3fff::/32");
+        assert_eq!(ip_placeholder("2a00:1450:4001:81c::1"), "3fff::1");
+        assert_eq!(ip_placeholder("2a00:1450:4001::/48"), "3fff::/48");
+    }
+
+    #[test]
+    fn test_networks_are_rebuilt_from_the_map() {
+        let mut map = HashMap::new();
+        ip_placeholder("10.0.0.0/16", &mut map, &mut IpNetworks::default()).unwrap();
+        ip_placeholder("10.0.5.17", &mut map, &mut IpNetworks::default()).unwrap();
+
+        let fake = ip_placeholder("10.0.6.1", &mut map, &mut IpNetworks::default()).unwrap();
+        assert_eq!(fake, "198.18.1.1");
+    }
+
+    #[test]
+    fn test_networks_do_not_wrap() {
+        let mut map = HashMap::new();
+        let mut networks = IpNetworks::default();
+        let fakes: HashSet<String> = (0..1000)
+            .map(|i| {
+                let ip = format!("10.{}.{}.1", i / 256, i % 256);
+                ip_placeholder(&ip, &mut map, &mut networks).unwrap()
+            })
+            .collect();
+
+        assert_eq!(fakes.len(), 1000);
+        assert!(ip_placeholder("8.0.0.0/2", &mut map, &mut networks).is_err());
+    }
+
+    #[test]
+    fn test_ipv6_keeps_subnets() {
+        let mut map = HashMap::new();
+        let mut networks = IpNetworks::default();
+        let mut ip_placeholder = |ip: &str| ip_placeholder(ip, &mut map, &mut networks).unwrap();
+
+        assert_eq!(
+            ip_placeholder("2a00:1450:4001:81c::200e"),
+            "2001:db8:0:1::1"
+        );
+        assert_eq!(
+            ip_placeholder("2a00:1450:4001:81c::/64"),
+            "2001:db8:0:1::/64"
+        );
+        assert_eq!(ip_placeholder("2a00:1450:4001:81c::1"), "2001:db8:0:1::2");
+        assert_eq!(ip_placeholder("fe80::1"), "2001:db8:0:2::1");
+        assert_eq!(ip_placeholder("fe80:0:0:0:0:0:0:1"), "2001:db8:0:2::1");
+    }
+}
@@ -0,0 +1,140 @@
+use crate::engine::{Category, Span};
+use crate::error::PatternError;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+
+/// Jira project keys: an uppercase letter followed by uppercase letters or digits.
+pub const DEFAULT_PROJECT_KEY_PATTERN: &str = "[A-Z][A-Z0-9]+";
+
+// Standards and hashes that look like issue keys: `UTF-8`, `SHA-256`, `ISO-8601`
+const NOT_PROJECTS: &[&str] = &[
+    "AES", "CVE", "COVID", "GPT", "HTTP", "ISO", "MD", "RFC", "RSA", "SHA", "SSL", "TLS", "UTF",
+];
+
+/// Finds Jira-style issue keys (`PAY-1423`), including inside Atlassian URLs,
+/// with a configurable project key pattern.
+#[derive(Debug, Clone, Default)]
+pub struct IssueMatcher {
+    regex: Option<Regex>,
+    project: Option<Regex>,
+}
+
+impl IssueMatcher {
+    pub fn new(project_pattern: &str) -> Result<Self, PatternError> {
+        Ok(Self {
+            regex: Some(Regex::new(&format!(r"\b({})-(\d+)\b", project_pattern))?),
+            project: Some(Regex::new(&format!("^(?:{})$", project_pattern))?),
+        })
+    }
+
+    /// Issue keys in `text`, keyed by the whole key (`PAY-1423`).
+    pub fn detect(&self, text: &str) -> Vec<Span> {
+        let Some(regex) = &self.regex else {
+            return Vec::new();
+        };
+
+        regex
+            .captures_iter(text)
+            .filter(|caps| !NOT_PROJECTS.contains(&&caps[1]))
+            .map(|caps| {
+                let key = caps.get(0).unwrap();
+                Span::new(key.range(), Category::Issue, vec![key.as_str().to_string()])
+            })
+            .collect()
+    }
+
+    /// Whether `segment` on its own is a project key, e.g. `PAY` in
+    /// `https://acme.atlassian.net/jira/software/projects/PAY/boards/1`.
+    pub fn is_project(&self, segment: &str) -> bool {
+        self.project
+            .as_ref()
+            .is_some_and(|project| project.is_match(segment))
+            && !NOT_PROJECTS.contains(&segment)
+    }
+}
+
+/// Maps the project key to a stable `PROJn` placeholder: `PAY-1423` → `PROJ1-1423`.
+///
+/// Unless `keep_numbers` is set, the issue number is replaced too and numbered
+/// per project (`PROJ1-1`, `PROJ1-2`, ...). A bare project key (`PAY`) maps to `PROJ1`.
+pub(crate) fn issue_placeholder(
+    key: &str,
+    keep_numbers: bool,
+    map: &mut HashMap<String, String>,
+) -> String {
+    if let Some(anonymous) = map.get(key) {
+        return anonymous.clone();
+    }
+    let (project, number) = key.split_once('-').unwrap_or((key, ""));
+
+    let fake_project = match map.get(project) {
+        Some(fake_project) => fake_project.clone(),
+        None => {
+            let projects: HashSet<&String> = map
+                .values()
+                .filter(|anonymous| !anonymous.contains('-'))
+                .collect();
+            let fake_project = format!("PROJ{}", projects.len() + 1);
+            map.insert(project.to_string(), fake_project.clone());
+            fake_project
+        }
+    };
+    if number.is_empty() {
+        return fake_project;
+    }
+    if keep_numbers {
+        return format!("{}-{}", fake_project, number);
+    }
+
+    let prefix = format!("{}-", fake_project);
+    let issues = map
+        .values()
+        .filter(|anonymous| anonymous.starts_with(&prefix))
+        .count();
+    let anonymous = format!("{}{}", prefix, issues + 1);
+    map.insert(key.to_string(), anonymous.clone());
+    anonymous
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_issue_keys() {
+        let matcher = IssueMatcher::new(DEFAULT_PROJECT_KEY_PATTERN).unwrap();
+        let text = "PAY-1423 blocks https://acme.atlassian.net/browse/OPS2-7, not UTF-8 or SHA-256 or pay-1";
+        let found: Vec<&str> = matcher
+            .detect(text)
+            .iter()
+            .map(|span| &text[span.start..span.end])
+            .collect();
+
+        assert_eq!(found, vec!["PAY-1423", "OPS2-7"]);
+        assert!(matcher.is_project("PAY"));
+        assert!(!matcher.is_project("boards"));
+    }
+
+    #[test]
+    fn test_custom_project_pattern() {
+        let matcher = IssueMatcher::new("PAY|OPS").unwrap();
+        let text = "PAY-1 and OPS-2 but not ABC-3";
+
+        assert_eq!(matcher.detect(text).len(), 2);
+    }
+
+    #[test]
+    fn test_issue_placeholder() {
+        let mut map = HashMap::new();
+
+        assert_eq!(issue_placeholder("PAY-1423", true, &mut map), "PROJ1-1423");
+        assert_eq!(issue_placeholder("OPS-7", true, &mut map), "PROJ2-7");
+        assert_eq!(issue_placeholder("PAY", true, &mut map), "PROJ1");
+
+        let mut map = HashMap::new();
+        assert_eq!(issue_placeholder("PAY-1423", false, &mut map), "PROJ1-1");
+        assert_eq!(issue_placeholder("PAY-17", false, &mut map), "PROJ1-2");
+        assert_eq!(issue_placeholder("PAY-1423", false, &mut map), "PROJ1-1");
+        assert_eq!(issue_placeholder("OPS-1423", false, &mut map), "PROJ2-1");
+    }
+}
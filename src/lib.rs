@@ -1,8 +1,33 @@
 pub mod error;
+pub mod allowlist;
+pub mod config;
+pub mod domains;
+pub mod engine;
+pub mod ips;
+pub mod issues;
+pub mod masking;
 pub mod patterns;
 pub mod legend;
+pub mod repos;
 pub mod anonymizer;
+pub mod export;
+pub mod formats;
+pub mod fake_names;
+pub mod persons;
+pub mod pseudonyms;
+pub mod roster;
+pub mod slack_ids;
+pub mod templates;
+pub mod restore;
 
-pub use error::{AnonymizationError, PatternError, LegendError};
-pub use legend::{AnonymizationMap, format_legend};
-pub use anonymizer::{Options, anonymize_text};
\ No newline at end of file
+pub use error::{AnonymizationError, PatternError, LegendError, ExportError, RosterError, ConfigError};
+pub use allowlist::Allowlist;
+pub use config::Config;
+pub use engine::Category;
+pub use masking::{DomainMask, MaskRule};
+pub use legend::{AnonymizationMap, format_kept, format_legend, parse_legend};
+pub use anonymizer::{Anonymizer, AnonymizerBuilder, Detector, Options, anonymize_text, anonymize_text_with_map};
+pub use export::anonymize_export;
+pub use pseudonyms::PseudonymKey;
+pub use restore::restore_text;
+pub use roster::{Roster, RosterEntry};
\ No newline at end of file
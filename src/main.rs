@@ -1,8 +1,12 @@
 use clap::Parser;
 use std::fs;
 use std::io::{self, Read};
+use std::path::Path;
 
-use slack_anonymizer::{Options, anonymize_text, format_legend};
+use slack_anonymizer::{
+    AnonymizationMap, Anonymizer, Config, Detector, MaskRule, Options, Roster, format_kept,
+    parse_legend, restore_text, templates::template_categories,
+};
 
 #[derive(Parser)]
 #[command(name = "slack-anonymizer")]
@@ -22,11 +26,136 @@ struct Args {
     #[arg(long)]
     replace: Option<String>,
 
+    /// Comma-separated list of owned domains (e.g. acme.com,corp.internal). Hostnames
+    /// under them are anonymized and share one fake domain with emails and URLs
+    #[arg(long, value_name = "LIST")]
+    domains: Option<String>,
+
+    /// Comma-separated list of extra URL query parameters whose values are redacted,
+    /// on top of the defaults (token, password, email, ...)
+    #[arg(long, value_name = "LIST")]
+    query_keys: Option<String>,
+
+    /// Comma-separated list of domains whose URLs and emails are kept, on top of the
+    /// defaults (github.com, docs.rs, stackoverflow.com, ...). `*.acme.net` allows subdomains
+    #[arg(long, value_name = "LIST")]
+    allow: Option<String>,
+
+    /// Print the URLs and emails kept by the allowlist to stderr
+    #[arg(long)]
+    report: bool,
+
+    /// Replace Jira issue keys, PAY-1423 → PROJ1-1423, also inside Atlassian URLs
+    #[arg(long)]
+    issues: bool,
+
+    /// Regex for the project part of issue keys (default: [A-Z][A-Z0-9]+)
+    #[arg(long, value_name = "REGEX")]
+    issue_pattern: Option<String>,
+
+    /// Number issues per project instead of keeping their numbers, PAY-1423 → PROJ1-1
+    #[arg(long)]
+    renumber_issues: bool,
+
+    /// Replace repository references and commit SHAs,
+    /// acme/payments-service#412 → org1/repo1#412, also in GitHub/GitLab/Bitbucket URLs
+    #[arg(long)]
+    repos: bool,
+
+    /// Comma-separated list of detectors to switch off, e.g. channels,display_names.
+    /// Detectors: users, display_names, channels, slack_ids, emails, phones, ips,
+    /// hostnames, urls, url_credentials, issues, repos, keywords, secrets
+    #[arg(long, value_name = "LIST")]
+    disable: Option<String>,
+
+    /// Comma-separated list of the only detectors to run, e.g. emails,phones,secrets.
+    /// Opt-in detectors like urls are switched on by naming them
+    #[arg(long, value_name = "LIST")]
+    only: Option<String>,
+
+    /// Placeholder template for a category, e.g. user=<PERSON_{n}> or all=[REDACTED].
+    /// Variables: {n}, {category}, {CATEGORY}, {default}, {len}, {shape}, {hash}. Repeatable
+    #[arg(long, value_name = "CATEGORY=TEMPLATE")]
+    placeholder: Vec<String>,
+
+    /// Mask a category instead of replacing it, keeping the shape of its values:
+    /// email → j***.s***@a***.com, phone → +44 ** **** 0958. Rules after a colon set
+    /// the characters kept and the domain, e.g. email:start=2,end=1,domain=keep|tld|mask.
+    /// `all` masks every category. Repeatable
+    #[arg(long, value_name = "CATEGORY[:RULE]")]
+    mask: Vec<String>,
+
+    /// Replace values with ones of the same length and character classes, for logs
+    /// and tables: 10.0.12.34 → 73.5.90.18, Jon Snow → Qvx Mobr. Comma-separated
+    /// categories, or all when no list is given
+    #[arg(long, value_name = "LIST", num_args = 0..=1, default_missing_value = "all")]
+    preserve_format: Option<String>,
+
     /// Print anonymization legend after output
     #[arg(long)]
     legend: bool,
+
+    /// Restore anonymized text (e.g. an LLM answer) using a saved legend or map file
+    #[arg(long, value_name = "LEGEND")]
+    restore: Option<String>,
+
+    /// JSON file with the anonymization map. Loaded before anonymizing (if it exists)
+    /// and updated afterwards, so placeholders stay consistent between runs
+    #[arg(long, value_name = "PATH")]
+    map_file: Option<String>,
+
+    /// Link handles, display names and emails of the same person into one identity
+    /// (@person1, Person1, person1@example.com)
+    #[arg(long)]
+    link_people: bool,
+
+    /// Replace people with plausible fake names, handles and emails
+    /// (Riley Morgan, @riley.morgan, riley.morgan@example.com) instead of person1
+    #[arg(long)]
+    realistic: bool,
+
+    /// Seed for the fake names of --realistic, so output is reproducible (default: 0)
+    #[arg(long, value_name = "N")]
+    seed: Option<u64>,
+
+    /// File with a team-shared secret key. Users, channels, emails and keywords get
+    /// keyed pseudonyms (@user-7f3a9c01ab2e) that match on every machine and run without
+    /// a map file, and {hash} works in templates. The SLACK_ANONYMIZER_KEY environment
+    /// variable can hold the key instead
+    #[arg(long, value_name = "PATH")]
+    pseudonym_key_file: Option<String>,
+
+    /// Slack users.json or CSV (id,handle,real_name,email) of known members.
+    /// Every form of a member's identity gets the same placeholder
+    #[arg(long, value_name = "PATH")]
+    roster: Option<String>,
+
+    /// Anonymize a Slack workspace export (directory or .zip) instead of plain text
+    #[arg(
+        long,
+        value_name = "EXPORT",
+        requires = "output",
+        conflicts_with = "input"
+    )]
+    export: Option<String>,
+
+    /// Where to write the anonymized export (a .zip path produces an archive)
+    #[arg(long, value_name = "PATH", requires = "export")]
+    output: Option<String>,
+
+    /// TOML config file. By default $XDG_CONFIG_HOME/slack-anonymizer/config.toml
+    /// and ./.slack-anonymizer.toml are used if they exist. Flags override config values
+    #[arg(long, value_name = "PATH", conflicts_with = "no_config")]
+    config: Option<String>,
+
+    /// Ignore config files
+    #[arg(long)]
+    no_config: bool,
 }
 
+// Holds the pseudonym key when no key file is given
+const PSEUDONYM_KEY_ENV: &str = "SLACK_ANONYMIZER_KEY";
+
 fn main() {
     let args = Args::parse();
 
@@ -37,9 +166,16 @@ fn main() {
 }
 
 fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
+    let config = load_config(&args)?;
+    let args = apply_config(args, &config);
+
+    if args.export.is_some() {
+        return run_export(args, &config);
+    }
+
     // Read input
-    let input = match args.input {
-        Some(file_path) => fs::read_to_string(&file_path)
+    let input = match &args.input {
+        Some(file_path) => fs::read_to_string(file_path)
             .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?,
         None => {
             let mut buffer = String::new();
@@ -48,38 +184,245 @@ fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
-    // Parse keywords
-    let keywords = if let Some(keyword_str) = args.replace {
-        keyword_str
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect()
-    } else {
-        Vec::new()
-    };
+    if let Some(legend_path) = args.restore {
+        let legend = fs::read_to_string(&legend_path)
+            .map_err(|e| format!("Failed to read legend '{}': {}", legend_path, e))?;
+        // Map files are JSON objects, anything else is treated as a legend
+        let map = if legend.trim_start().starts_with('{') {
+            AnonymizationMap::from_json(&legend)?
+        } else {
+            parse_legend(&legend)?
+        };
+
+        print!("{}", restore_text(&input, &map)?);
+        return Ok(());
+    }
 
-    // Set up options
-    let options = Options::new(args.urls, keywords);
+    let mut anonymizer = build_anonymizer(&args, &config)?;
 
     // Anonymize text
-    let (anonymized, map) = anonymize_text(&input, &options)?;
+    let anonymized = anonymizer.anonymize(&input)?;
+    save_map(&args, &anonymizer)?;
 
     // Output result
     print!("{}", anonymized);
+    print_legend(&args, &anonymizer)?;
+    print_report(&args, &anonymizer);
+
+    Ok(())
+}
+
+fn run_export(args: Args, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let (Some(input), Some(output)) = (&args.export, &args.output) else {
+        return Err("--export requires --output".into());
+    };
+
+    let mut anonymizer = build_anonymizer(&args, config)?;
+
+    anonymizer
+        .anonymize_export(Path::new(input), Path::new(output))
+        .map_err(|e| format!("Failed to anonymize export '{}': {}", input, e))?;
+    save_map(&args, &anonymizer)?;
+    print_legend(&args, &anonymizer)?;
+    print_report(&args, &anonymizer);
+
+    Ok(())
+}
+
+fn load_config(args: &Args) -> Result<Config, Box<dyn std::error::Error>> {
+    if args.no_config {
+        return Ok(Config::default());
+    }
+    let config = Config::discover(args.config.as_deref().map(Path::new))
+        .map_err(|e| format!("Failed to load config: {}", e))?;
+    Ok(config)
+}
+
+// Config values for settings not given as flags
+fn apply_config(mut args: Args, config: &Config) -> Args {
+    let path =
+        |path: &Option<std::path::PathBuf>| path.as_ref().map(|path| path.display().to_string());
+    args.map_file = args.map_file.or_else(|| path(&config.map_file));
+    args.roster = args.roster.or_else(|| path(&config.roster));
+    args.legend |= config.legend.show.unwrap_or(false);
+    args.report |= config.legend.report.unwrap_or(false);
+    args
+}
+
+// Flags extend the config's lists and override its other values
+fn parse_options(args: &Args, config: &Config) -> Result<Options, Box<dyn std::error::Error>> {
+    let mut options = config.options();
+    if args.only.is_some() {
+        options.only(&parse_detectors(args.only.as_deref())?);
+    }
+    options.anonymize_urls |= args.urls;
+    options.link_people |= args.link_people;
+    options.realistic_names |= args.realistic;
+    if let Some(seed) = args.seed {
+        options.seed = seed;
+    }
+    options.issue_keys |= args.issues;
+    options.keep_issue_numbers &= !args.renumber_issues;
+    options.repo_refs |= args.repos;
+    options.keywords.extend(split_list(args.replace.as_deref()));
+    options
+        .owned_domains
+        .extend(split_list(args.domains.as_deref()));
+    options
+        .sensitive_query_keys
+        .extend(split_list(args.query_keys.as_deref()));
+    options.allowlist.extend(split_list(args.allow.as_deref()));
+    if let Some(pattern) = &args.issue_pattern {
+        options.issue_key_pattern = pattern.clone();
+    }
+    for detector in parse_detectors(args.disable.as_deref())? {
+        options.set_enabled(detector, false);
+    }
+    if let Some(key) = pseudonym_key(args, config)? {
+        options.pseudonym_key = Some(key);
+    }
+    for spec in &args.placeholder {
+        let categories = spec
+            .split_once('=')
+            .and_then(|(name, template)| Some((template_categories(name)?, template)));
+        let Some((categories, template)) = categories else {
+            return Err(format!(
+                "Invalid placeholder '{}' (expected CATEGORY=TEMPLATE)",
+                spec
+            )
+            .into());
+        };
+        for category in categories {
+            options.placeholders.insert(category, template.to_string());
+        }
+    }
+    for name in split_list(args.preserve_format.as_deref()) {
+        let Some(categories) = template_categories(&name) else {
+            return Err(format!("Unknown category '{}' in --preserve-format", name).into());
+        };
+        options.preserve_format.extend(categories);
+    }
+    for spec in &args.mask {
+        let (name, rule) = spec.split_once(':').unwrap_or((spec, ""));
+        let Some(categories) = template_categories(name) else {
+            return Err(format!("Invalid mask '{}' (expected CATEGORY[:RULE])", spec).into());
+        };
+        let rule: MaskRule = rule.parse()?;
+        for category in categories {
+            options.masks.insert(category, rule);
+        }
+    }
+    Ok(options)
+}
+
+// The key file flag wins over the environment, which wins over the config
+fn pseudonym_key(
+    args: &Args,
+    config: &Config,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let read = |path: &Path| {
+        fs::read_to_string(path).map_err(|e| {
+            format!(
+                "Failed to read pseudonym key file {}: {}",
+                path.display(),
+                e
+            )
+        })
+    };
+    if let Some(path) = &args.pseudonym_key_file {
+        return Ok(Some(read(Path::new(path))?));
+    }
+    if let Some(key) = std::env::var(PSEUDONYM_KEY_ENV)
+        .ok()
+        .filter(|key| !key.is_empty())
+    {
+        return Ok(Some(key));
+    }
+    match &config.pseudonym_key_file {
+        Some(path) => Ok(Some(read(path)?)),
+        None => Ok(None),
+    }
+}
+
+fn parse_detectors(list: Option<&str>) -> Result<Vec<Detector>, Box<dyn std::error::Error>> {
+    split_list(list)
+        .iter()
+        .map(|name| {
+            Detector::from_name(name).ok_or_else(|| {
+                let names: Vec<&str> = Detector::ALL.iter().map(|d| d.name()).collect();
+                format!(
+                    "Unknown detector '{}' (expected one of: {})",
+                    name,
+                    names.join(", ")
+                )
+                .into()
+            })
+        })
+        .collect()
+}
+
+// Comma-separated CLI list
+fn split_list(list: Option<&str>) -> Vec<String> {
+    list.map(|list| {
+        list.split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+// Load map from previous runs and seed it from the roster
+fn build_anonymizer(
+    args: &Args,
+    config: &Config,
+) -> Result<Anonymizer, Box<dyn std::error::Error>> {
+    let mut builder = Anonymizer::builder().options(parse_options(args, config)?);
+
+    if let Some(path) = &args.map_file {
+        let map = AnonymizationMap::load(path)
+            .map_err(|e| format!("Failed to load map file '{}': {}", path, e))?;
+        builder = builder.map(map);
+    }
+
+    if let Some(path) = &args.roster {
+        let roster =
+            Roster::load(path).map_err(|e| format!("Failed to load roster '{}': {}", path, e))?;
+        builder = builder.roster(roster);
+    }
+
+    Ok(builder.build()?)
+}
 
-    // Output legend if requested
+fn save_map(args: &Args, anonymizer: &Anonymizer) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(path) = &args.map_file {
+        anonymizer
+            .save_map(path)
+            .map_err(|e| format!("Failed to save map file '{}': {}", path, e))?;
+    }
+    Ok(())
+}
+
+// Output legend if requested
+fn print_legend(args: &Args, anonymizer: &Anonymizer) -> Result<(), Box<dyn std::error::Error>> {
     if args.legend {
-        let legend = format_legend(&map)?;
+        let legend = anonymizer.legend()?;
         if !legend.is_empty() {
             print!("{}", legend);
         }
     }
-
     Ok(())
 }
 
+// Stderr, so piping the output into an LLM never includes it
+fn print_report(args: &Args, anonymizer: &Anonymizer) {
+    if args.report {
+        eprint!("{}", format_kept(anonymizer.map()));
+    }
+}
+
 #[cfg(test)]
+#[allow(unused_imports, clippy::needless_borrows_for_generic_args)]
 mod tests {
     use super::*;
     use std::io::Write;
@@ -92,7 +435,13 @@ mod tests {
         writeln!(temp_file, "Hey @john, check #general").unwrap();
 
         let output = Command::new("cargo")
-            .args(&["run", "--", temp_file.path().to_str().unwrap()])
+            .args(&[
+                "run",
+                "--",
+                "--no-config",
+                temp_file.path().to_str().unwrap(),
+            ])
+            .env_remove("SLACK_ANONYMIZER_KEY")
             .output()
             .unwrap();
 
@@ -107,7 +456,14 @@ mod tests {
         writeln!(temp_file, "Hey @john, check #general").unwrap();
 
         let output = Command::new("cargo")
-            .args(&["run", "--", temp_file.path().to_str().unwrap(), "--legend"])
+            .args(&[
+                "run",
+                "--",
+                "--no-config",
+                temp_file.path().to_str().unwrap(),
+                "--legend",
+            ])
+            .env_remove("SLACK_ANONYMIZER_KEY")
             .output()
             .unwrap();
 
@@ -123,7 +479,14 @@ mod tests {
         writeln!(temp_file, "Visit https://company.com").unwrap();
 
         let output = Command::new("cargo")
-            .args(&["run", "--", temp_file.path().to_str().unwrap(), "--urls"])
+            .args(&[
+                "run",
+                "--",
+                "--no-config",
+                temp_file.path().to_str().unwrap(),
+                "--urls",
+            ])
+            .env_remove("SLACK_ANONYMIZER_KEY")
             .output()
             .unwrap();
 
@@ -140,14 +503,390 @@ mod tests {
             .args(&[
                 "run",
                 "--",
+                "--no-config",
                 temp_file.path().to_str().unwrap(),
                 "--replace",
                 "ProjectX",
             ])
+            .env_remove("SLACK_ANONYMIZER_KEY")
             .output()
             .unwrap();
 
         let stdout = String::from_utf8(output.stdout).unwrap();
         assert!(stdout.contains("keyword1"));
     }
+
+    #[test]
+    fn test_cli_restore() {
+        let mut legend_file = NamedTempFile::new().unwrap();
+        writeln!(legend_file, "=== ANONYMIZATION LEGEND ===").unwrap();
+        writeln!(legend_file, "@john → @user1").unwrap();
+        writeln!(legend_file, "#general → #ch1").unwrap();
+
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(input_file, "User1 will post the summary in #ch1").unwrap();
+
+        let output = Command::new("cargo")
+            .args([
+                "run",
+                "--",
+                "--no-config",
+                input_file.path().to_str().unwrap(),
+                "--restore",
+                legend_file.path().to_str().unwrap(),
+            ])
+            .env_remove("SLACK_ANONYMIZER_KEY")
+            .output()
+            .unwrap();
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(stdout, "john will post the summary in #general\n");
+    }
+
+    #[test]
+    fn test_cli_with_roster() {
+        let mut roster_file = tempfile::Builder::new().suffix(".csv").tempfile().unwrap();
+        writeln!(roster_file, "id,handle,real_name,email").unwrap();
+        writeln!(roster_file, "U0123ABC,jon.snow,Jon Snow,jon.snow@corp.com").unwrap();
+
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(
+            input_file,
+            "Jon Snow (@jon.snow) wrote from jon.snow@corp.com"
+        )
+        .unwrap();
+
+        let output = Command::new("cargo")
+            .args([
+                "run",
+                "--",
+                "--no-config",
+                input_file.path().to_str().unwrap(),
+                "--roster",
+                roster_file.path().to_str().unwrap(),
+            ])
+            .env_remove("SLACK_ANONYMIZER_KEY")
+            .output()
+            .unwrap();
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(stdout, "user1 (@user1) wrote from user1@domain1.com\n");
+    }
+
+    #[test]
+    fn test_cli_link_people() {
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(input_file, "Jon Snow asked @jon.snow").unwrap();
+
+        let output = Command::new("cargo")
+            .args([
+                "run",
+                "--",
+                "--no-config",
+                input_file.path().to_str().unwrap(),
+                "--link-people",
+            ])
+            .env_remove("SLACK_ANONYMIZER_KEY")
+            .output()
+            .unwrap();
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(stdout, "Person1 asked @person1\n");
+    }
+
+    #[test]
+    fn test_cli_realistic_names() {
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(input_file, "Jon Snow asked @jon.snow").unwrap();
+        let run = |seed: &str| {
+            let output = Command::new("cargo")
+                .args([
+                    "run",
+                    "--",
+                    "--no-config",
+                    input_file.path().to_str().unwrap(),
+                    "--realistic",
+                    "--seed",
+                    seed,
+                ])
+                .env_remove("SLACK_ANONYMIZER_KEY")
+                .output()
+                .unwrap();
+            String::from_utf8(output.stdout).unwrap()
+        };
+
+        let stdout = run("42");
+        assert_eq!(stdout, run("42"));
+        assert!(!stdout.contains("Jon Snow") && !stdout.contains("person1"));
+    }
+
+    #[test]
+    fn test_cli_pseudonym_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_file = dir.path().join("key");
+        std::fs::write(&key_file, "team secret\n").unwrap();
+        let input_file = dir.path().join("input.txt");
+        std::fs::write(&input_file, "@jon posted in #general").unwrap();
+
+        let output = Command::new("cargo")
+            .args([
+                "run",
+                "--",
+                "--no-config",
+                input_file.to_str().unwrap(),
+                "--pseudonym-key-file",
+                key_file.to_str().unwrap(),
+            ])
+            .env_remove("SLACK_ANONYMIZER_KEY")
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(stdout.starts_with("@user-") && stdout.contains(" posted in #ch-"));
+
+        // The same key from the environment gives the same pseudonyms
+        let output = Command::new("cargo")
+            .args(["run", "--", "--no-config", input_file.to_str().unwrap()])
+            .env("SLACK_ANONYMIZER_KEY", "team secret")
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), stdout);
+    }
+
+    #[test]
+    fn test_cli_masks() {
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(
+            input_file,
+            "@jon: mail jon.snow@acme.com or call +44 20 7946 0958"
+        )
+        .unwrap();
+
+        let output = Command::new("cargo")
+            .args([
+                "run",
+                "--",
+                "--no-config",
+                input_file.path().to_str().unwrap(),
+                "--mask",
+                "email",
+                "--mask",
+                "phone:end=2",
+            ])
+            .env_remove("SLACK_ANONYMIZER_KEY")
+            .output()
+            .unwrap();
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(
+            stdout,
+            "@user1: mail j***.s***@a***.com or call +44 ** **** **58\n"
+        );
+    }
+
+    #[test]
+    fn test_cli_preserve_format() {
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(input_file, "12:00 10.0.12.34 @jon  denied").unwrap();
+
+        let output = Command::new("cargo")
+            .args([
+                "run",
+                "--",
+                "--no-config",
+                input_file.path().to_str().unwrap(),
+                "--preserve-format",
+            ])
+            .env_remove("SLACK_ANONYMIZER_KEY")
+            .output()
+            .unwrap();
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let fields: Vec<&str> = stdout.split(' ').collect();
+        assert_eq!(stdout.len(), "12:00 10.0.12.34 @jon  denied\n".len());
+        assert!(fields[1] != "10.0.12.34" && fields[2] != "@jon");
+    }
+
+    #[test]
+    fn test_cli_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let export_dir = dir.path().join("export");
+        std::fs::create_dir_all(export_dir.join("general")).unwrap();
+        std::fs::write(
+            export_dir.join("channels.json"),
+            r#"[{"id": "C024BE7LR", "name": "general"}]"#,
+        )
+        .unwrap();
+        std::fs::write(
+            export_dir.join("general/2024-01-15.json"),
+            r#"[{"user": "U024BE7LH", "text": "Hey @john, see #general"}]"#,
+        )
+        .unwrap();
+        let output_dir = dir.path().join("anonymized");
+
+        let output = Command::new("cargo")
+            .args([
+                "run",
+                "--",
+                "--no-config",
+                "--export",
+                export_dir.to_str().unwrap(),
+                "--output",
+                output_dir.to_str().unwrap(),
+                "--legend",
+            ])
+            .env_remove("SLACK_ANONYMIZER_KEY")
+            .output()
+            .unwrap();
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(stdout.contains("#general → #ch1"));
+
+        let messages = std::fs::read_to_string(output_dir.join("ch1/2024-01-15.json")).unwrap();
+        assert!(messages.contains("@user2, see #ch1"));
+        assert!(!messages.contains("U024BE7LH"));
+    }
+
+    #[test]
+    fn test_cli_config_with_flag_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        std::fs::write(
+            &config_path,
+            "keywords = [\"ProjectX\"]\n\n[legend]\nshow = true\n",
+        )
+        .unwrap();
+
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(input_file, "ProjectX ships with ClientABC").unwrap();
+
+        let output = Command::new("cargo")
+            .args([
+                "run",
+                "--",
+                input_file.path().to_str().unwrap(),
+                "--config",
+                config_path.to_str().unwrap(),
+                "--replace",
+                "ClientABC",
+            ])
+            .env_remove("SLACK_ANONYMIZER_KEY")
+            .output()
+            .unwrap();
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(stdout.starts_with("keyword1 ships with keyword2\n"));
+        assert!(stdout.contains("=== ANONYMIZATION LEGEND ==="));
+    }
+
+    #[test]
+    fn test_cli_disable_and_only() {
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(input_file, "@jon: see #incidents, mail jon@corp.com").unwrap();
+        let run = |flag: &str, list: &str| {
+            Command::new("cargo")
+                .args([
+                    "run",
+                    "--",
+                    "--no-config",
+                    input_file.path().to_str().unwrap(),
+                    flag,
+                    list,
+                ])
+                .env_remove("SLACK_ANONYMIZER_KEY")
+                .output()
+                .unwrap()
+        };
+
+        let output = run("--disable", "channels");
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(stdout, "@user1: see #incidents, mail user1@domain1.com\n");
+
+        let output = run("--only", "emails");
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(stdout, "@jon: see #incidents, mail user1@domain1.com\n");
+
+        let output = run("--only", "email");
+        assert!(!output.status.success());
+        assert!(
+            String::from_utf8(output.stderr)
+                .unwrap()
+                .contains("Unknown detector 'email'")
+        );
+    }
+
+    #[test]
+    fn test_cli_placeholder_templates() {
+        let dir = tempfile::tempdir().unwrap();
+        let map_path = dir.path().join("vault.json");
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(input_file, "@jon: see #incidents").unwrap();
+
+        let output = Command::new("cargo")
+            .args([
+                "run",
+                "--",
+                "--no-config",
+                input_file.path().to_str().unwrap(),
+                "--placeholder",
+                "all=<{CATEGORY}_{n}>",
+                "--placeholder",
+                "user=<PERSON_{n}>",
+                "--map-file",
+                map_path.to_str().unwrap(),
+            ])
+            .env_remove("SLACK_ANONYMIZER_KEY")
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(stdout, "<PERSON_1>: see <CHANNEL_1>\n");
+
+        let mut answer_file = NamedTempFile::new().unwrap();
+        writeln!(answer_file, "Ask <PERSON_1> in <CHANNEL_1>").unwrap();
+        let output = Command::new("cargo")
+            .args([
+                "run",
+                "--",
+                "--no-config",
+                answer_file.path().to_str().unwrap(),
+                "--restore",
+                map_path.to_str().unwrap(),
+            ])
+            .env_remove("SLACK_ANONYMIZER_KEY")
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(stdout, "Ask @jon in #incidents\n");
+    }
+
+    #[test]
+    fn test_cli_map_file_keeps_placeholders_between_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        let map_path = dir.path().join("vault.json");
+
+        let mut first = NamedTempFile::new().unwrap();
+        writeln!(first, "Hey @john and @jane").unwrap();
+        let mut second = NamedTempFile::new().unwrap();
+        writeln!(second, "@bob replied to @jane").unwrap();
+
+        let run_with_map = |input: &NamedTempFile| {
+            Command::new("cargo")
+                .args([
+                    "run",
+                    "--",
+                    "--no-config",
+                    input.path().to_str().unwrap(),
+                    "--map-file",
+                    map_path.to_str().unwrap(),
+                ])
+                .env_remove("SLACK_ANONYMIZER_KEY")
+                .output()
+                .unwrap()
+        };
+
+        assert!(run_with_map(&first).status.success());
+        let output = run_with_map(&second);
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(stdout, "@user3 replied to @user2\n");
+    }
 }
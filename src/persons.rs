@@ -0,0 +1,127 @@
+use crate::patterns::Taken;
+use std::collections::{HashMap, HashSet};
+
+/// Normalizes a handle, display name or email address to the key that
+/// identifies a person: `@jon.snow`, `Jon Snow` and `jon.snow@corp.com`
+/// all become `jon snow`.
+pub fn person_key(original: &str) -> String {
+    let name = original.trim().trim_start_matches('@');
+    let name = name.split_once('@').map_or(name, |(local, _)| local);
+
+    name.split(|c: char| c == '.' || c == '_' || c == '-' || c.is_whitespace())
+        .filter(|part| !part.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns the person (`person1`, `person2`, ...) any of `originals` belongs to,
+/// registering a new person if none of them is known, and links all of them to it.
+pub fn link_person(originals: &[&str], persons: &mut HashMap<String, String>) -> String {
+    numbered_person(originals, persons, &mut Taken::default())
+}
+
+/// Like [`link_person`], with `taken` kept next to `persons` across calls.
+pub(crate) fn numbered_person(
+    originals: &[&str],
+    persons: &mut HashMap<String, String>,
+    taken: &mut Taken,
+) -> String {
+    link_person_with(originals, persons, taken, |people| {
+        format!("person{}", people.len() + 1)
+    })
+}
+
+/// Like [`link_person`], naming a new person with `new_person`, which gets the
+/// people known so far.
+pub(crate) fn link_person_with(
+    originals: &[&str],
+    persons: &mut HashMap<String, String>,
+    taken: &mut Taken,
+    new_person: impl FnOnce(&HashSet<String>) -> String,
+) -> String {
+    let keys: Vec<String> = originals
+        .iter()
+        .map(|original| person_key(original))
+        .filter(|key| !key.is_empty())
+        .collect();
+
+    let person = match keys.iter().find_map(|key| persons.get(key)) {
+        Some(person) => person.clone(),
+        None => new_person(taken.values(persons)),
+    };
+
+    for key in keys {
+        taken.insert(persons, &key, &person);
+    }
+
+    person
+}
+
+/// `person1` → `@person1`
+pub fn person_handle(person: &str) -> String {
+    format!("@{}", person)
+}
+
+/// `person1` → `Person1`, `riley.morgan` → `Riley Morgan`
+pub fn person_name(person: &str) -> String {
+    person
+        .split('.')
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// `person1` → `person1@example.com`
+pub fn person_email(person: &str) -> String {
+    format!("{}@example.com", person)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_person_key() {
+        assert_eq!(person_key("@jon.snow"), "jon snow");
+        assert_eq!(person_key("Jon Snow"), "jon snow");
+        assert_eq!(person_key("Jon.Snow@Corp.com"), "jon snow");
+        assert_eq!(person_key("@Jon_Snow"), "jon snow");
+    }
+
+    #[test]
+    fn test_link_person() {
+        let mut persons = HashMap::new();
+
+        assert_eq!(link_person(&["@jon.snow"], &mut persons), "person1");
+        assert_eq!(link_person(&["Aria Stark"], &mut persons), "person2");
+        assert_eq!(link_person(&["Jon Snow"], &mut persons), "person1");
+        assert_eq!(
+            link_person(
+                &["U024BE7LH", "@jon.snow", "jon.snow@corp.com"],
+                &mut persons
+            ),
+            "person1"
+        );
+        assert_eq!(persons.get("u024be7lh"), Some(&"person1".to_string()));
+        assert_eq!(link_person(&["jon.snow@corp.com"], &mut persons), "person1");
+        assert_eq!(
+            link_person(&["aria-stark@corp.com"], &mut persons),
+            "person2"
+        );
+    }
+
+    #[test]
+    fn test_person_forms() {
+        assert_eq!(person_handle("person3"), "@person3");
+        assert_eq!(person_name("person3"), "Person3");
+        assert_eq!(person_email("person3"), "person3@example.com");
+        assert_eq!(person_name("riley.morgan"), "Riley Morgan");
+    }
+}
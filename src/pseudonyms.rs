@@ -0,0 +1,113 @@
+use crate::engine::Category;
+use crate::error::PatternError;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::fmt;
+
+/// Hex digits of a `{hash}`, `@user-7f3a9c01ab2e`. At 48 bits, two of a
+/// million values share a hash with a chance of about 1 in 500
+pub(crate) const HASH_LEN: usize = 12;
+
+/// Templates of the pseudonym mode for categories without one of their own.
+///
+/// Phones, IPs, hosts, URLs, issues, repos, commits and secrets keep their
+/// default placeholders, which preserve their shape; a `{hash}` template opts them in.
+pub(crate) fn pseudonym_template(category: Category) -> Option<&'static str> {
+    match category {
+        Category::User => Some("@user-{hash}"),
+        Category::DisplayName => Some("name-{hash}"),
+        Category::Channel => Some("#ch-{hash}"),
+        Category::Workspace => Some("workspace-{hash}"),
+        Category::Email => Some("user-{hash}@example.com"),
+        Category::Keyword => Some("keyword-{hash}"),
+        _ => None,
+    }
+}
+
+/// Secret key of keyed pseudonyms: the placeholder of a value is derived from an
+/// HMAC-SHA256 of the value, so it is the same on every machine and every run
+/// without sharing a map, and can't be linked back to the value without the key.
+#[derive(Clone)]
+pub struct PseudonymKey {
+    mac: Hmac<Sha256>,
+}
+
+impl PseudonymKey {
+    pub fn new(key: &str) -> Result<Self, PatternError> {
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(PatternError::InvalidKey("the key is empty".to_string()));
+        }
+        let mac = Hmac::new_from_slice(key.as_bytes())
+            .map_err(|e| PatternError::InvalidKey(e.to_string()))?;
+        Ok(Self { mac })
+    }
+
+    /// A key of its own for a map without a pseudonym key, see
+    /// [`AnonymizationMap::secret_salt`](crate::AnonymizationMap::secret_salt).
+    pub(crate) fn salt(salt: &str) -> Self {
+        // HMAC takes keys of any length
+        let mac = Hmac::new_from_slice(salt.as_bytes()).unwrap();
+        Self { mac }
+    }
+
+    /// Full hex HMAC of a value. Sigils, surrounding whitespace and case are
+    /// ignored, so `@Jon` and `jon` get the same pseudonym.
+    pub fn hash(&self, value: &str) -> String {
+        self.hash_exact(&normalize(value))
+    }
+
+    /// Full hex HMAC of a value as it is, e.g. a case-sensitive secret.
+    pub fn hash_exact(&self, value: &str) -> String {
+        let mut mac = self.mac.clone();
+        mac.update(value.as_bytes());
+        mac.finalize()
+            .into_bytes()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+}
+
+/// The key of a value its pseudonym is derived from: the handle or name when
+/// it comes with one, which every form of a mention shares (`@jon`,
+/// `<@U024BE7LH|jon>`), else its first key.
+pub(crate) fn pseudonym_source(keys: &[String]) -> &str {
+    keys.iter()
+        .find(|key| key.starts_with(['@', '#']))
+        .unwrap_or(&keys[0])
+}
+
+// The key stays out of logs
+impl fmt::Debug for PseudonymKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PseudonymKey(..)")
+    }
+}
+
+fn normalize(value: &str) -> String {
+    value.trim().trim_start_matches(['@', '#']).to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_is_keyed_and_normalized() {
+        let key = PseudonymKey::new("team secret").unwrap();
+
+        assert_eq!(key.hash("@Jon.Snow"), key.hash("jon.snow"));
+        assert_eq!(key.hash("@jon.snow").len(), 64);
+        assert_ne!(key.hash("@jon.snow"), key.hash("@aria"));
+        assert_eq!(
+            key.hash("@jon.snow"),
+            PseudonymKey::new("team secret").unwrap().hash("@jon.snow")
+        );
+        assert_ne!(
+            key.hash("@jon.snow"),
+            PseudonymKey::new("other secret").unwrap().hash("@jon.snow")
+        );
+        assert!(PseudonymKey::new("  ").is_err());
+    }
+}
@@ -0,0 +1,232 @@
+use crate::engine::{Category, Span};
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+// `acme/payments-service#412` and `acme/payments-service@a1b2c3d`
+const SHORTHAND_PATTERN: &str =
+    r"\b([A-Za-z0-9][A-Za-z0-9-]*)/([A-Za-z0-9._-]*[A-Za-z0-9_-])(?:#\d+|@([0-9a-f]{7,40})\b)";
+// `github.com/acme/payments-service/pull/412`, with or without a scheme
+const HOSTED_PATTERN: &str = r"(?i)\b(?:www\.)?(?:github\.com|gitlab\.com|bitbucket\.org)/([A-Za-z0-9][A-Za-z0-9-]*)(?:/([A-Za-z0-9._-]*[A-Za-z0-9_-]))?";
+// Abbreviated (7+) or full commit SHAs
+const SHA_PATTERN: &str = r"\b[0-9a-f]{7,40}\b";
+
+static SHORTHAND_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(SHORTHAND_PATTERN).unwrap());
+static HOSTED_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(HOSTED_PATTERN).unwrap());
+static SHA_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(SHA_PATTERN).unwrap());
+
+// First path segments of code hosting sites that are not organizations
+const NOT_ORGS: &[&str] = &[
+    "apps",
+    "explore",
+    "features",
+    "login",
+    "marketplace",
+    "notifications",
+    "orgs",
+    "search",
+    "settings",
+    "sponsors",
+    "topics",
+    "users",
+];
+
+/// Finds repository references: `org/repo#412`, `org/repo@sha`, GitHub, GitLab
+/// and Bitbucket URLs, and commit SHAs.
+///
+/// Organizations are keyed by name (`acme`) and repositories by their full name
+/// (`acme/payments-service`), so shorthand references and URLs share placeholders.
+/// PR and issue numbers are kept, so the LLM can still tell which PR is which.
+pub fn detect_repos(text: &str) -> Vec<Span> {
+    let mut spans = Vec::new();
+
+    for caps in SHORTHAND_RE.captures_iter(text) {
+        let (org, repo) = (caps.get(1).unwrap(), caps.get(2).unwrap());
+        let before = text[..org.start()].chars().next_back();
+        if before.is_some_and(|c| "/.:@#".contains(c)) {
+            continue;
+        }
+        let key = format!("{}/{}", org.as_str(), repo.as_str());
+        spans.push(Span::new(
+            org.start()..repo.end(),
+            Category::Repo,
+            vec![key],
+        ));
+    }
+
+    for caps in HOSTED_RE.captures_iter(text) {
+        let org = caps.get(1).unwrap();
+        if NOT_ORGS.contains(&org.as_str().to_lowercase().as_str()) {
+            continue;
+        }
+        let (end, key) = match caps.get(2) {
+            Some(repo) => {
+                let repo_name = repo.as_str().trim_end_matches(".git");
+                let end = repo.start() + repo_name.len();
+                (end, format!("{}/{}", org.as_str(), repo_name))
+            }
+            None => (org.end(), org.as_str().to_string()),
+        };
+        spans.push(Span::new(org.start()..end, Category::Repo, vec![key]));
+    }
+
+    spans.extend(
+        SHA_RE
+            .find_iter(text)
+            .filter(|m| is_sha(m.as_str()))
+            .map(|m| Span::new(m.range(), Category::Commit, vec![m.as_str().to_string()])),
+    );
+
+    spans
+}
+
+// Hex words (`deadbeef`) and plain numbers are no SHAs
+fn is_sha(candidate: &str) -> bool {
+    candidate.chars().any(|c| c.is_ascii_digit())
+        && candidate.chars().any(|c| c.is_ascii_lowercase())
+}
+
+/// `acme` → `org1`, `acme/payments-service` → `org1/repo1`. Names are
+/// case-insensitive, like on GitHub.
+pub(crate) fn repo_placeholder(key: &str, map: &mut HashMap<String, String>) -> String {
+    let key = key.to_lowercase();
+    if let Some(anonymous) = map.get(&key) {
+        return anonymous.clone();
+    }
+    let Some((org, _)) = key.split_once('/') else {
+        return org_placeholder(&key, map);
+    };
+
+    let fake_org = org_placeholder(org, map);
+    let repos = map
+        .values()
+        .filter(|anonymous| anonymous.contains('/'))
+        .count();
+    let anonymous = format!("{}/repo{}", fake_org, repos + 1);
+    map.insert(key, anonymous.clone());
+    anonymous
+}
+
+fn org_placeholder(org: &str, map: &mut HashMap<String, String>) -> String {
+    if let Some(anonymous) = map.get(org) {
+        return anonymous.clone();
+    }
+
+    let orgs = map
+        .values()
+        .filter(|anonymous| !anonymous.contains('/'))
+        .count();
+    let anonymous = format!("org{}", orgs + 1);
+    map.insert(org.to_string(), anonymous.clone());
+    anonymous
+}
+
+/// Replaces a commit SHA with a fake one of the same length (`c000001…`).
+///
+/// An abbreviated SHA and the full SHA it abbreviates stay consistent in both
+/// directions: the fake of the short one is a prefix of the fake of the full one.
+pub(crate) fn commit_placeholder(sha: &str, map: &mut HashMap<String, String>) -> String {
+    if let Some(anonymous) = map.get(sha) {
+        return anonymous.clone();
+    }
+
+    // A longer SHA this one abbreviates, or a shorter one abbreviating it
+    let related = map
+        .iter()
+        .filter(|(original, _)| original.starts_with(sha) || sha.starts_with(original.as_str()))
+        .max_by_key(|(original, _)| original.len());
+
+    let anonymous = match related {
+        Some((_, fake)) if fake.len() >= sha.len() => fake[..sha.len()].to_string(),
+        Some((_, fake)) => extend_sha(fake, sha),
+        None => {
+            let commits = map
+                .values()
+                .filter(|fake| fake.len() >= 7)
+                .map(|fake| &fake[..7]);
+            let count = commits.collect::<HashSet<_>>().len();
+            extend_sha(&format!("c{:06x}", count + 1), sha)
+        }
+    };
+    map.insert(sha.to_string(), anonymous.clone());
+    anonymous
+}
+
+// Pads `prefix` to the length of `sha` with hex derived from it
+fn extend_sha(prefix: &str, sha: &str) -> String {
+    let hash = Sha256::digest(sha.as_bytes());
+    let padding: String = hash.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+    let padding = padding.repeat(3);
+    let mut fake = prefix.to_string();
+    fake.push_str(&padding[..sha.len().saturating_sub(prefix.len())]);
+    fake.truncate(sha.len());
+    fake
+}
+
+/// Whether `anonymous` is a fake SHA made by [`commit_placeholder`].
+pub fn is_fake_sha(anonymous: &str) -> bool {
+    (7..=40).contains(&anonymous.len())
+        && anonymous.starts_with("c0")
+        && anonymous.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detected(text: &str) -> Vec<&str> {
+        let mut spans = detect_repos(text);
+        spans.sort_by_key(|span| span.start);
+        spans
+            .iter()
+            .map(|span| &text[span.start..span.end])
+            .collect()
+    }
+
+    #[test]
+    fn test_detect_repos() {
+        let text = "See acme/payments-service#412, https://github.com/acme/payments-service/pull/412 \
+                    and gitlab.com/acme/infra.git at a1b2c3d, not deadbeef, 1234567 or https://x.com/docs/api#12";
+
+        assert_eq!(
+            detected(text),
+            vec![
+                "acme/payments-service",
+                "acme/payments-service",
+                "acme/infra",
+                "a1b2c3d"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_repo_placeholder() {
+        let mut map = HashMap::new();
+
+        assert_eq!(
+            repo_placeholder("acme/payments-service", &mut map),
+            "org1/repo1"
+        );
+        assert_eq!(repo_placeholder("acme/infra", &mut map), "org1/repo2");
+        assert_eq!(repo_placeholder("Acme", &mut map), "org1");
+        assert_eq!(repo_placeholder("globex/infra", &mut map), "org2/repo3");
+    }
+
+    #[test]
+    fn test_short_and_full_shas_stay_consistent() {
+        let mut map = HashMap::new();
+        let full = "a1b2c3d4e5f60718293a4b5c6d7e8f9012345678";
+
+        let short = commit_placeholder("a1b2c3d", &mut map);
+        let long = commit_placeholder(full, &mut map);
+        assert_eq!(short, "c000001");
+        assert_eq!(long.len(), 40);
+        assert!(long.starts_with(&short));
+        assert_eq!(commit_placeholder("a1b2c3d4e", &mut map), long[..9]);
+
+        let other = commit_placeholder("ffee1234", &mut map);
+        assert!(other.starts_with("c000002") && other.len() == 8);
+        assert!(is_fake_sha(&long));
+    }
+}
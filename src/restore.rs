@@ -0,0 +1,379 @@
+use crate::error::PatternError;
+use crate::legend::AnonymizationMap;
+use std::collections::HashMap;
+
+/// Reverses every placeholder found in `text` back to its original value.
+///
+/// Matching is case-insensitive, so placeholders re-cased by an LLM (`User1`)
+/// are restored too. A placeholder only matches on word boundaries, which keeps
+/// `user10` from being read as `user1` followed by `0`. A trailing plural `s`
+/// or a possessive (`user1s`, `user1's`) is kept after the restored value.
+///
+/// Format-preserving replacements look like ordinary words, so they are only
+/// restored with their exact case.
+///
+/// Secret placeholders (`[SECRET1]`) stay as they are: the map only holds
+/// their fingerprints. So do tags shared by several values, e.g. `[REDACTED]`.
+pub fn restore_text(text: &str, map: &AnonymizationMap) -> Result<String, PatternError> {
+    let reverse = Placeholders::new(reverse_map(map));
+    let formats = Placeholders::new(reverse_formats(map));
+    if reverse.is_empty() && formats.is_empty() {
+        return Ok(text.to_string());
+    }
+
+    // Placeholders are looked up in the lowercased text, format-preserving
+    // replacements in the text itself
+    let (lower, offsets) = lowercase(text);
+    let fits = |end: usize| is_boundary_after(text, end);
+
+    let mut result = String::with_capacity(text.len());
+    let mut last_end = 0;
+    let mut lower_start = 0;
+    for (start, c) in text.char_indices() {
+        let at = lower_start;
+        lower_start += c.to_lowercase().map(char::len_utf8).sum::<usize>();
+        if start < last_end || !is_boundary_before(text, start) {
+            continue;
+        }
+
+        let exact = formats.longest(text, start, fits);
+        let folded = reverse
+            .longest(&lower, at, |end| offsets[end].is_some_and(fits))
+            .map(|(end, original)| (offsets[end].unwrap_or(end), original));
+        // The longest wins, so that `user10` beats `user1`
+        // and `user1@domain1.com` beats `user1`
+        let found = match (exact, folded) {
+            (Some(exact), Some(folded)) if folded.0 > exact.0 => Some(folded),
+            (exact, folded) => exact.or(folded),
+        };
+        let Some((end, original)) = found else {
+            continue;
+        };
+
+        result.push_str(&text[last_end..start]);
+        result.push_str(original);
+        last_end = end;
+    }
+    result.push_str(&text[last_end..]);
+
+    Ok(result)
+}
+
+// Placeholder → original lookup, with the lengths of the placeholders by their
+// first character, so that a text is scanned with a few lookups per word
+// instead of one pattern per placeholder
+struct Placeholders {
+    originals: HashMap<String, String>,
+    lengths: HashMap<char, Vec<usize>>,
+}
+
+impl Placeholders {
+    fn new(originals: HashMap<String, String>) -> Self {
+        let mut lengths: HashMap<char, Vec<usize>> = HashMap::new();
+        for placeholder in originals.keys() {
+            if let Some(c) = placeholder.chars().next() {
+                lengths.entry(c).or_default().push(placeholder.len());
+            }
+        }
+        for lengths in lengths.values_mut() {
+            lengths.sort_unstable_by(|a, b| b.cmp(a));
+            lengths.dedup();
+        }
+        Self { originals, lengths }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.originals.is_empty()
+    }
+
+    // The longest placeholder at `start` whose end `fits`, with its original
+    fn longest(
+        &self,
+        text: &str,
+        start: usize,
+        fits: impl Fn(usize) -> bool,
+    ) -> Option<(usize, &String)> {
+        let c = text[start..].chars().next()?;
+        self.lengths.get(&c)?.iter().find_map(|&len| {
+            let end = start + len;
+            let candidate = text.get(start..end)?;
+            let original = self.originals.get(candidate)?;
+            fits(end).then_some((end, original))
+        })
+    }
+}
+
+// `text` lowercased, with the offset in `text` of each byte of the result that
+// ends the lowercase form of a character. Case folds that lowercasing doesn't
+// map, like `ſ` for `s`, are left alone
+fn lowercase(text: &str) -> (String, Vec<Option<usize>>) {
+    let mut lower = String::with_capacity(text.len());
+    let mut offsets = vec![Some(0)];
+    for (i, c) in text.char_indices() {
+        lower.extend(c.to_lowercase());
+        offsets.resize(lower.len(), None);
+        offsets.push(Some(i + c.len_utf8()));
+    }
+    (lower, offsets)
+}
+
+/// Builds a lowercase placeholder → original lookup table.
+///
+/// Exact placeholders are inserted first; derived forms (a user placeholder
+/// without its `@`, the bare host of an anonymized URL) only fill gaps.
+fn reverse_map(map: &AnonymizationMap) -> HashMap<String, String> {
+    let mut reverse = HashMap::new();
+
+    let categories = [
+        &map.users,
+        &map.display_names,
+        &map.channels,
+        &map.workspaces,
+        &map.messages,
+        &map.emails,
+        &map.phones,
+        &map.ips,
+        &map.domains,
+        &map.urls,
+        &map.issues,
+        &map.repos,
+        &map.commits,
+        &map.keywords,
+    ];
+
+    for category in categories {
+        // Sort for a deterministic choice when two originals share a placeholder
+        let mut entries: Vec<(&String, &String)> = category.iter().collect();
+        entries.sort();
+
+        for (original, anonymous) in entries {
+            reverse
+                .entry(anonymous.to_lowercase())
+                .or_insert_with(|| original.clone());
+        }
+    }
+
+    // LLMs often drop the `@` from mentions, e.g. "user1 reported that..."
+    for (original, anonymous) in &map.users {
+        if let (Some(original), Some(anonymous)) =
+            (original.strip_prefix('@'), anonymous.strip_prefix('@'))
+        {
+            reverse
+                .entry(anonymous.to_lowercase())
+                .or_insert_with(|| original.to_string());
+        }
+    }
+
+    // Tags from templates stand in for the default placeholders. A tag keyed
+    // `user1` restores what `@user1` does, so the mention keeps its `@`
+    let ambiguous = map.ambiguous_tags();
+    let mut tags: Vec<(&String, &String)> = map.tags.iter().collect();
+    tags.sort();
+    for (key, tag) in tags {
+        if ambiguous.contains(tag.as_str()) {
+            continue;
+        }
+        let original = [format!("@{}", key), format!("#{}", key), key.clone()]
+            .iter()
+            .find_map(|anonymous| reverse.get(anonymous).cloned());
+        let Some(original) = original else {
+            continue;
+        };
+        // A display name tagged like its handle, `user-7f3a9c01ab2e` for `@user-7f3a9c01ab2e`
+        if let Some(bare) = tag.strip_prefix('@') {
+            reverse
+                .entry(bare.to_lowercase())
+                .or_insert_with(|| original.trim_start_matches('@').to_string());
+        }
+        reverse.insert(tag.to_lowercase(), original);
+    }
+
+    // URLs are often quoted without their path
+    for (original, anonymous) in &map.urls {
+        let (Some(original_host), Some(anonymous_host)) = (url_host(original), url_host(anonymous))
+        else {
+            continue;
+        };
+
+        reverse
+            .entry(anonymous_host.to_lowercase())
+            .or_insert_with(|| original_host.to_string());
+    }
+
+    reverse
+}
+
+/// Builds an exact placeholder → original lookup table of the format-preserving
+/// replacements that stand for exactly one original.
+fn reverse_formats(map: &AnonymizationMap) -> HashMap<String, String> {
+    let ambiguous = map.ambiguous_formats();
+    map.formats
+        .iter()
+        .filter(|(_, anonymous)| !ambiguous.contains(anonymous.as_str()))
+        .map(|(original, anonymous)| (anonymous.clone(), original.clone()))
+        .collect()
+}
+
+/// Returns the `scheme://host` part of a URL.
+fn url_host(url: &str) -> Option<&str> {
+    let protocol_end = url.find("://")? + 3;
+    let host_end = url[protocol_end..]
+        .find('/')
+        .map_or(url.len(), |pos| protocol_end + pos);
+    Some(&url[..host_end])
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn is_boundary_before(text: &str, pos: usize) -> bool {
+    text[..pos]
+        .chars()
+        .next_back()
+        .is_none_or(|c| !is_word_char(c))
+}
+
+fn is_boundary_after(text: &str, pos: usize) -> bool {
+    let mut rest = text[pos..].chars();
+    match rest.next() {
+        None => true,
+        // Plural form, e.g. "user1s"
+        Some('s') | Some('S') => rest.next().is_none_or(|c| !is_word_char(c)),
+        Some(c) => !is_word_char(c),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_map() -> AnonymizationMap {
+        let mut map = AnonymizationMap::new();
+        map.users
+            .insert("@jon.snow".to_string(), "@user1".to_string());
+        map.display_names
+            .insert("Aria Stark".to_string(), "name2".to_string());
+        map.channels
+            .insert("#general".to_string(), "#ch3".to_string());
+        map.emails.insert(
+            "support@company.com".to_string(),
+            "user1@domain1.com".to_string(),
+        );
+        map.urls.insert(
+            "https://company.com/docs".to_string(),
+            "https://example1.com/docs".to_string(),
+        );
+        map.keywords
+            .insert("projectx".to_string(), "keyword1".to_string());
+        map
+    }
+
+    #[test]
+    fn test_restore_all_categories() {
+        let map = sample_map();
+        let text = "@user1 asked name2 in #ch3 to mail user1@domain1.com about keyword1, see https://example1.com/docs";
+        let result = restore_text(text, &map).unwrap();
+
+        assert_eq!(
+            result,
+            "@jon.snow asked Aria Stark in #general to mail support@company.com about projectx, see https://company.com/docs"
+        );
+    }
+
+    #[test]
+    fn test_restore_recased_and_plural() {
+        let map = sample_map();
+        let text = "Name2 agreed with User1. NAME2's comment and user1s' notes";
+        let result = restore_text(text, &map).unwrap();
+
+        assert_eq!(
+            result,
+            "Aria Stark agreed with jon.snow. Aria Stark's comment and jon.snows' notes"
+        );
+    }
+
+    #[test]
+    fn test_restore_respects_word_boundaries() {
+        let mut map = AnonymizationMap::new();
+        map.users.insert("@alice".to_string(), "@user1".to_string());
+        map.users.insert("@bob".to_string(), "@user10".to_string());
+
+        let result = restore_text("@user10 and @user1 but not @user100", &map).unwrap();
+
+        assert_eq!(result, "@bob and @alice but not @user100");
+    }
+
+    #[test]
+    fn test_restore_url_without_path() {
+        let map = sample_map();
+        let result = restore_text("Docs live at https://example1.com", &map).unwrap();
+
+        assert_eq!(result, "Docs live at https://company.com");
+    }
+
+    #[test]
+    fn test_restore_tags() {
+        let mut map = sample_map();
+        map.display_names
+            .insert("Jon Snow".to_string(), "user1".to_string());
+        map.tags
+            .insert("user1".to_string(), "<PERSON_1>".to_string());
+        map.tags.insert("ch3".to_string(), "[REDACTED]".to_string());
+        map.tags
+            .insert("keyword1".to_string(), "[REDACTED]".to_string());
+
+        let result = restore_text("<PERSON_1> asked in [REDACTED] about keyword1", &map).unwrap();
+
+        assert_eq!(result, "@jon.snow asked in [REDACTED] about projectx");
+    }
+
+    #[test]
+    fn test_restore_formats() {
+        let mut map = AnonymizationMap::new();
+        map.formats
+            .insert("Jon Snow".to_string(), "Qvx Mobr".to_string());
+        map.formats
+            .insert("10.0.12.34".to_string(), "73.5.90.18".to_string());
+
+        let result = restore_text("Qvx Mobr logged in from 73.5.90.18", &map).unwrap();
+
+        assert_eq!(result, "Jon Snow logged in from 10.0.12.34");
+
+        // Other casings are ordinary words
+        map.formats.insert("Aria".to_string(), "Will".to_string());
+        let result = restore_text("Will will QVX MOBR", &map).unwrap();
+        assert_eq!(result, "Aria will QVX MOBR");
+    }
+
+    #[test]
+    fn test_restore_ignores_unmapped_case_folds() {
+        let map = sample_map();
+        // `ſ` folds to `s`, but doesn't lowercase to it
+        let result = restore_text("#ch3 and keyword1 vs ſ @uſer1", &map).unwrap();
+
+        assert_eq!(result, "#general and projectx vs ſ @uſer1");
+    }
+
+    #[test]
+    fn test_restore_large_map() {
+        let mut map = AnonymizationMap::new();
+        for n in 1..=50_000 {
+            map.users
+                .insert(format!("@member{}", n), format!("@user{}", n));
+            map.display_names
+                .insert(format!("Member {}", n), format!("name{}", n));
+        }
+
+        let result = restore_text("@user1 thanked USER49999 and name50000", &map).unwrap();
+        assert_eq!(result, "@member1 thanked member49999 and Member 50000");
+    }
+
+    #[test]
+    fn test_restore_empty_map() {
+        let map = AnonymizationMap::new();
+        let result = restore_text("@user1 in #ch1", &map).unwrap();
+
+        assert_eq!(result, "@user1 in #ch1");
+    }
+}
@@ -0,0 +1,389 @@
+use crate::anonymizer::Options;
+use crate::error::{AnonymizationError, RosterError};
+use crate::fake_names::{FakeNames, KnownWords, link_fake_person};
+use crate::legend::AnonymizationMap;
+use crate::patterns::{Taken, email_placeholder, user_keys, user_placeholder};
+use crate::persons::{numbered_person, person_email, person_handle, person_name};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// Display names shorter than this are left to the detectors: short ones are
+/// often ordinary words (`Max`, `Will`) that would be replaced everywhere.
+const MIN_DISPLAY_NAME_LEN: usize = 5;
+
+/// A known workspace member.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RosterEntry {
+    pub id: Option<String>,
+    pub handle: Option<String>,
+    pub real_name: Option<String>,
+    pub display_name: Option<String>,
+    pub email: Option<String>,
+}
+
+impl RosterEntry {
+    /// Names to replace wherever they appear: the real name, and the display
+    /// name unless it is too short to tell from an ordinary word.
+    fn names(&self) -> impl Iterator<Item = &String> {
+        self.real_name.iter().chain(
+            self.display_name
+                .iter()
+                .filter(|name| name.chars().count() >= MIN_DISPLAY_NAME_LEN),
+        )
+    }
+
+    /// Everything that names the member: ID, handle, email and names.
+    fn originals(&self) -> Vec<&str> {
+        [&self.id, &self.handle, &self.email]
+            .into_iter()
+            .flatten()
+            .chain(self.names())
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// A list of workspace members used to preload the anonymization map,
+/// so that every way of referring to a person resolves to one placeholder.
+#[derive(Debug, Clone, Default)]
+pub struct Roster {
+    pub entries: Vec<RosterEntry>,
+}
+
+impl Roster {
+    /// Loads a roster from a Slack `users.json` file, or from a CSV file
+    /// (`id,handle,real_name,email`) when the path ends with `.csv`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, AnonymizationError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)?;
+
+        if path.extension().is_some_and(|ext| ext == "csv") {
+            Ok(Self::from_csv(&contents)?)
+        } else {
+            Ok(Self::from_users_json(&contents)?)
+        }
+    }
+
+    /// Parses the `users.json` file of a Slack export.
+    pub fn from_users_json(json: &str) -> Result<Self, RosterError> {
+        let value: Value =
+            serde_json::from_str(json).map_err(|e| RosterError::InvalidJson(e.to_string()))?;
+        let users = value
+            .as_array()
+            .ok_or_else(|| RosterError::InvalidJson("expected an array of users".to_string()))?;
+
+        let entries = users
+            .iter()
+            .map(|user| {
+                let field = |value: &Value, key: &str| {
+                    value
+                        .get(key)
+                        .and_then(Value::as_str)
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                        .map(str::to_string)
+                };
+                let profile = user.get("profile").unwrap_or(&Value::Null);
+
+                RosterEntry {
+                    id: field(user, "id"),
+                    handle: field(user, "name"),
+                    real_name: field(user, "real_name").or_else(|| field(profile, "real_name")),
+                    display_name: field(profile, "display_name"),
+                    email: field(profile, "email"),
+                }
+            })
+            .collect();
+
+        Ok(Self { entries })
+    }
+
+    /// Parses `id,handle,real_name,email` rows. A header row starting with
+    /// `id` is skipped and empty fields are allowed.
+    pub fn from_csv(csv: &str) -> Result<Self, RosterError> {
+        let mut entries = Vec::new();
+
+        for (index, line) in csv.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let fields = split_csv_line(line);
+            if index == 0 && fields.first().is_some_and(|f| f.eq_ignore_ascii_case("id")) {
+                continue;
+            }
+            if fields.len() != 4 {
+                return Err(RosterError::InvalidCsv(format!(
+                    "line {}: expected 4 fields (id,handle,real_name,email), found {}",
+                    index + 1,
+                    fields.len()
+                )));
+            }
+
+            let field = |i: usize| Some(fields[i].clone()).filter(|s| !s.is_empty());
+            entries.push(RosterEntry {
+                id: field(0),
+                handle: field(1).map(|h| h.trim_start_matches('@').to_string()),
+                real_name: field(2),
+                display_name: None,
+                email: field(3),
+            });
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Preloads `map` so that the ID, `@handle`, names and email of each
+    /// member share one placeholder number: `@user1`, `user1`, `user1@domain1.com`,
+    /// or `@person1`, `Person1`, `person1@example.com` when linking people
+    /// (fake names like `@riley.morgan` in the realistic mode).
+    pub fn seed(&self, map: &mut AnonymizationMap, options: &Options) {
+        let realistic = options
+            .realistic_names
+            .then(|| FakeNames::new(options.seed));
+        let (mut taken, mut known) = (Taken::default(), KnownWords::default());
+        let mut emails = Taken::default();
+        // No fake may be the real name of a member seeded later
+        known.reserve(self.entries.iter().flat_map(RosterEntry::originals));
+        for entry in &self.entries {
+            if options.link_people || options.realistic_names {
+                seed_person(entry, realistic.as_ref(), map, &mut taken, &mut known);
+                continue;
+            }
+
+            // Without an ID or handle, the "@Name Surname" mention form identifies the person
+            let handle = entry
+                .handle
+                .clone()
+                .or_else(|| entry.real_name.clone())
+                .or_else(|| entry.display_name.clone());
+            if entry.id.is_none() && handle.is_none() {
+                continue;
+            }
+
+            let keys = user_keys(entry.id.as_deref(), handle.as_deref());
+            let anonymous = user_placeholder(&keys, &mut map.users, &mut taken);
+            let person = anonymous.trim_start_matches('@');
+
+            for name in entry.names() {
+                map.display_names
+                    .entry(name.clone())
+                    .or_insert_with(|| person.to_string());
+            }
+
+            if let Some(email) = &entry.email {
+                let number = person.trim_start_matches("user");
+                let anonymous = format!("user{}@domain{}.com", number, number);
+                if emails.contains(&map.emails, &anonymous) {
+                    // Another address already has this number, e.g. in a loaded map
+                    email_placeholder(email, &mut map.emails, &mut emails, |_| None);
+                } else {
+                    emails.insert(&mut map.emails, email, &anonymous);
+                }
+            }
+        }
+    }
+}
+
+fn seed_person(
+    entry: &RosterEntry,
+    realistic: Option<&FakeNames>,
+    map: &mut AnonymizationMap,
+    taken: &mut Taken,
+    known: &mut KnownWords,
+) {
+    let originals = entry.originals();
+    if originals.is_empty() {
+        return;
+    }
+
+    let person = match realistic {
+        Some(fake_names) => {
+            link_fake_person(&originals, &mut map.persons, taken, known, fake_names, "")
+        }
+        None => numbered_person(&originals, &mut map.persons, taken),
+    };
+
+    let handle = entry.handle.as_ref().map(|h| format!("@{}", h));
+    for key in entry.id.iter().chain(handle.iter()) {
+        map.users
+            .entry(key.clone())
+            .or_insert_with(|| person_handle(&person));
+    }
+    for name in entry.names() {
+        map.display_names
+            .entry(name.clone())
+            .or_insert_with(|| person_name(&person));
+    }
+    if let Some(email) = &entry.email {
+        map.emails
+            .entry(email.clone())
+            .or_insert_with(|| person_email(&person));
+    }
+}
+
+// Splits one CSV row, honouring double-quoted fields ("Snow, Jon")
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field).trim().to_string()),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field.trim().to_string());
+
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anonymizer::anonymize_text_with_map;
+
+    const USERS_JSON: &str = r#"[
+        {
+            "id": "U0123ABC",
+            "name": "jon.snow",
+            "real_name": "Jon Snow",
+            "profile": {"display_name": "Jon", "email": "jon.snow@corp.com"}
+        },
+        {"id": "U0456DEF", "name": "madonna", "profile": {"real_name": "Madonna"}}
+    ]"#;
+
+    #[test]
+    fn test_from_users_json() {
+        let roster = Roster::from_users_json(USERS_JSON).unwrap();
+
+        assert_eq!(roster.entries.len(), 2);
+        assert_eq!(
+            roster.entries[0],
+            RosterEntry {
+                id: Some("U0123ABC".to_string()),
+                handle: Some("jon.snow".to_string()),
+                real_name: Some("Jon Snow".to_string()),
+                display_name: Some("Jon".to_string()),
+                email: Some("jon.snow@corp.com".to_string()),
+            }
+        );
+        assert_eq!(roster.entries[1].real_name, Some("Madonna".to_string()));
+    }
+
+    #[test]
+    fn test_from_csv() {
+        let csv = "id,handle,real_name,email\nU0123ABC,@jon.snow,\"Snow, Jon\",jon.snow@corp.com\n,,Madonna,\n";
+        let roster = Roster::from_csv(csv).unwrap();
+
+        assert_eq!(roster.entries.len(), 2);
+        assert_eq!(roster.entries[0].handle, Some("jon.snow".to_string()));
+        assert_eq!(roster.entries[0].real_name, Some("Snow, Jon".to_string()));
+        assert_eq!(roster.entries[1].id, None);
+        assert_eq!(roster.entries[1].real_name, Some("Madonna".to_string()));
+    }
+
+    #[test]
+    fn test_from_csv_wrong_field_count() {
+        assert!(Roster::from_csv("U0123ABC,jon.snow\n").is_err());
+    }
+
+    #[test]
+    fn test_seed_links_every_form_of_a_person() {
+        let roster = Roster::from_users_json(USERS_JSON).unwrap();
+        let mut map = AnonymizationMap::new();
+        let options = Options::default();
+        roster.seed(&mut map, &options);
+
+        let text = "@jon.snow (Jon Snow, <@U0123ABC>) mailed jon.snow@corp.com. Madonna agreed";
+        let result = anonymize_text_with_map(text, &options, &mut map).unwrap();
+
+        assert_eq!(
+            result,
+            "@user1 (user1, @user1) mailed user1@domain1.com. user2 agreed"
+        );
+    }
+
+    #[test]
+    fn test_seed_keeps_email_placeholders_distinct() {
+        let roster = Roster::from_users_json(USERS_JSON).unwrap();
+        let mut map = AnonymizationMap::new();
+        map.emails
+            .insert("ops@corp.com".to_string(), "user1@domain1.com".to_string());
+        let options = Options::default();
+        roster.seed(&mut map, &options);
+
+        assert_eq!(map.users["@jon.snow"], "@user1");
+        assert_eq!(map.emails["jon.snow@corp.com"], "user2@domain2.com");
+    }
+
+    #[test]
+    fn test_seed_with_linked_people() {
+        let roster = Roster::from_users_json(USERS_JSON).unwrap();
+        let mut map = AnonymizationMap::new();
+        let options = Options {
+            link_people: true,
+            ..Options::default()
+        };
+        roster.seed(&mut map, &options);
+
+        let text = "Jon Snow (@jon.snow, <@U0123ABC>) mailed jon.snow@corp.com. Madonna agreed";
+        let result = anonymize_text_with_map(text, &options, &mut map).unwrap();
+
+        assert_eq!(
+            result,
+            "Person1 (@person1, @person1) mailed person1@example.com. Person2 agreed"
+        );
+    }
+
+    #[test]
+    fn test_seed_fakes_avoid_every_member() {
+        let roster = Roster::from_csv(
+            "U1,jon.snow,Jon Snow,\nU2,river.turner,River Turner,\nU3,cameron.price,,",
+        )
+        .unwrap();
+        let mut map = AnonymizationMap::new();
+        let options = Options {
+            realistic_names: true,
+            ..Options::default()
+        };
+        roster.seed(&mut map, &options);
+
+        let real = ["jon", "snow", "river", "turner", "cameron", "price"];
+        for fake in map.users.values() {
+            let fake = fake.trim_start_matches('@');
+            assert!(
+                !fake.split('.').any(|word| real.contains(&word)),
+                "{}",
+                fake
+            );
+        }
+    }
+
+    #[test]
+    fn test_seed_skips_short_display_names() {
+        let roster = Roster::from_users_json(
+            r#"[
+                {"id": "U0123ABC", "name": "will.turner", "profile": {"display_name": "Will"}},
+                {"id": "U0456DEF", "name": "aria", "profile": {"display_name": "Arya Stark"}}
+            ]"#,
+        )
+        .unwrap();
+        let mut map = AnonymizationMap::new();
+        let options = Options::default();
+        roster.seed(&mut map, &options);
+
+        let text = "Will Arya Stark review it? We will see, @will.turner";
+        let result = anonymize_text_with_map(text, &options, &mut map).unwrap();
+
+        assert_eq!(result, "Will user2 review it? We will see, @user1");
+    }
+}
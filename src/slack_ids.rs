@@ -0,0 +1,161 @@
+use crate::engine::{Category, Span};
+use crate::patterns::Taken;
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+// Users (U, W), user groups (S), channels (C, G, D), workspaces (T) and
+// enterprise grids (E). Real IDs always contain a digit, `CONFIGURED` doesn't
+const SLACK_ID_PATTERN: &str = r"\b[UWSCGDTE][A-Z0-9]{8,11}\b";
+// `acme.slack.com`, `acme.enterprise.slack.com`
+const WORKSPACE_PATTERN: &str = r"(?i)\b([a-z0-9][a-z0-9-]*)\.(?:enterprise\.)?slack\.com\b";
+// `p1700000000123456` in permalinks, `1700000000.123456` in API output
+const MESSAGE_TS_PATTERN: &str = r"\bp?(\d{10})\.?(\d{6})\b";
+
+static SLACK_ID_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(SLACK_ID_PATTERN).unwrap());
+static WORKSPACE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(WORKSPACE_PATTERN).unwrap());
+static MESSAGE_TS_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(MESSAGE_TS_PATTERN).unwrap());
+
+// Slack's own subdomains, which say nothing about the workspace
+const NOT_WORKSPACES: &[&str] = &[
+    "a",
+    "api",
+    "app",
+    "ca",
+    "edgeapi",
+    "files",
+    "hooks",
+    "slack-files",
+    "status",
+    "www",
+];
+
+/// Finds raw Slack object IDs (`U024BE7LH`, `C024BE91L`), workspace subdomains
+/// and message timestamps, e.g. in a permalink like
+/// `https://acme.slack.com/archives/C024BE91L/p1700000000123456`.
+///
+/// User and channel IDs share their placeholders with the mentions and
+/// channel names an export or roster linked them to.
+pub fn detect_slack_ids(text: &str) -> Vec<Span> {
+    let ids = SLACK_ID_RE
+        .find_iter(text)
+        .filter(|m| m.as_str().chars().any(|c| c.is_ascii_digit()))
+        .map(|m| {
+            let category = match m.as_str().as_bytes()[0] {
+                b'U' | b'W' | b'S' => Category::User,
+                b'C' | b'G' | b'D' => Category::Channel,
+                _ => Category::Workspace,
+            };
+            Span::new(m.range(), category, vec![m.as_str().to_string()])
+        });
+
+    let workspaces = WORKSPACE_RE
+        .captures_iter(text)
+        .map(|caps| caps.get(1).unwrap())
+        .filter(|subdomain| !NOT_WORKSPACES.contains(&subdomain.as_str().to_lowercase().as_str()))
+        .map(|subdomain| {
+            let key = subdomain.as_str().to_lowercase();
+            Span::new(subdomain.range(), Category::Workspace, vec![key])
+        });
+
+    // Both forms of one timestamp share a key
+    let messages = MESSAGE_TS_RE
+        .captures_iter(text)
+        .filter(|caps| {
+            let ts = caps.get(0).unwrap().as_str();
+            ts.starts_with('p') != ts.contains('.')
+        })
+        .map(|caps| {
+            let key = format!("{}.{}", &caps[1], &caps[2]);
+            Span::new(caps.get(0).unwrap().range(), Category::Message, vec![key])
+        });
+
+    ids.chain(workspaces).chain(messages).collect()
+}
+
+/// `acme` or `T024BE7LD` → `workspace1`.
+pub(crate) fn workspace_placeholder(
+    key: &str,
+    map: &mut HashMap<String, String>,
+    taken: &mut Taken,
+) -> String {
+    numbered(key, "workspace", map, taken)
+}
+
+/// `1700000000.123456` → `msg1`.
+pub(crate) fn message_placeholder(
+    ts: &str,
+    map: &mut HashMap<String, String>,
+    taken: &mut Taken,
+) -> String {
+    numbered(ts, "msg", map, taken)
+}
+
+fn numbered(
+    key: &str,
+    prefix: &str,
+    map: &mut HashMap<String, String>,
+    taken: &mut Taken,
+) -> String {
+    if let Some(anonymous) = map.get(key) {
+        return anonymous.clone();
+    }
+
+    let anonymous = format!("{}{}", prefix, taken.next_number(map));
+    taken.insert(map, key, &anonymous);
+    anonymous
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detected(text: &str) -> Vec<(&str, Option<Category>)> {
+        let mut spans = detect_slack_ids(text);
+        spans.sort_by_key(|span| span.start);
+        spans
+            .iter()
+            .map(|span| (&text[span.start..span.end], span.category()))
+            .collect()
+    }
+
+    #[test]
+    fn test_detect_permalink() {
+        let text = "https://acme.slack.com/archives/C024BE91L/p1700000000123456?thread_ts=1700000000.000100";
+
+        assert_eq!(
+            detected(text),
+            vec![
+                ("acme", Some(Category::Workspace)),
+                ("C024BE91L", Some(Category::Channel)),
+                ("p1700000000123456", Some(Category::Message)),
+                ("1700000000.000100", Some(Category::Message)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_detect_raw_ids() {
+        let text = "user=U024BE7LH team=T024BE7LD group S0614TZR7, CONFIGURED on hooks.slack.com at 1700000000";
+
+        assert_eq!(
+            detected(text),
+            vec![
+                ("U024BE7LH", Some(Category::User)),
+                ("T024BE7LD", Some(Category::Workspace)),
+                ("S0614TZR7", Some(Category::User)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_message_forms_share_placeholder() {
+        let text = "p1700000000123456 and 1700000000.123456";
+        let keys: Vec<String> = detect_slack_ids(text)
+            .iter()
+            .map(|span| span.keys()[0].clone())
+            .collect();
+
+        assert_eq!(keys, vec!["1700000000.123456", "1700000000.123456"]);
+    }
+}
@@ -0,0 +1,298 @@
+use crate::engine::Category;
+use crate::error::PatternError;
+use crate::pseudonyms::HASH_LEN;
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// Variables a placeholder template can use:
+///
+/// - `{n}`: a number counted per template, `<PERSON_{n}>` → `<PERSON_1>`, `<PERSON_2>`
+/// - `{category}`, `{CATEGORY}`: the category name, `user` or `USER`
+/// - `{default}`: the default placeholder, `@user1`
+/// - `{len}`: the length of the original value in characters
+/// - `{shape}`: the original with letters as `x`/`X` and digits as `9`, `Xxx Xxxx`
+/// - `{hash}`: a keyed hash of the original, `@user-{hash}` → `@user-7f3a9c01ab2e`,
+///   see [`PseudonymKey`](crate::PseudonymKey)
+pub const TEMPLATE_VARIABLES: &[&str] = &[
+    "n", "category", "CATEGORY", "default", "len", "shape", "hash",
+];
+
+static VARIABLE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{(\w+)\}").unwrap());
+
+/// Categories a template applies to: one by name (`user`, `display_name`, ...)
+/// or every category for `all`.
+pub fn template_categories(name: &str) -> Option<Vec<Category>> {
+    if name.trim().eq_ignore_ascii_case("all") {
+        return Some(Category::ALL.to_vec());
+    }
+    Category::from_name(name).map(|category| vec![category])
+}
+
+/// `keyed` tells whether a pseudonym key is set, which `{hash}` needs.
+pub(crate) fn validate_template(template: &str, keyed: bool) -> Result<(), PatternError> {
+    for caps in VARIABLE_RE.captures_iter(template) {
+        if !TEMPLATE_VARIABLES.contains(&&caps[1]) {
+            return Err(PatternError::InvalidTemplate(format!(
+                "unknown variable {} in '{}'",
+                &caps[0], template
+            )));
+        }
+        if &caps[1] == "hash" && !keyed {
+            return Err(PatternError::InvalidTemplate(format!(
+                "{} in '{}' needs a pseudonym key",
+                &caps[0], template
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Key of a default placeholder in [`AnonymizationMap::tags`](crate::AnonymizationMap::tags).
+///
+/// Sigils and case are ignored, so a handle (`@user1`), a raw ID rendered as
+/// `user1` and a display name reusing the handle's placeholder share one tag.
+pub(crate) fn tag_key(default: &str) -> String {
+    default.trim_start_matches(['@', '#']).to_lowercase()
+}
+
+/// What the tags of a map already use, kept next to them like
+/// [`Taken`](crate::patterns::Taken) so that a new tag doesn't rescan them.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TagCache {
+    /// Full hash behind each `{hash}` tag, to tell collisions from repeats
+    pub(crate) hashes: HashMap<String, String>,
+    // Highest `{n}` per template and category
+    numbers: HashMap<(String, Category), usize>,
+    len: usize,
+}
+
+impl TagCache {
+    fn next_number(
+        &mut self,
+        template: &str,
+        category: Category,
+        tags: &HashMap<String, String>,
+    ) -> usize {
+        if tags.len() != self.len {
+            self.numbers.clear();
+            self.len = tags.len();
+        }
+        let number = self
+            .numbers
+            .entry((template.to_string(), category))
+            .or_insert_with(|| highest_number(template, category, tags));
+        *number += 1;
+        *number
+    }
+}
+
+/// Renders `template` for a value whose default placeholder is `default`.
+/// `hash` is the value's full keyed hash, if a pseudonym key is set.
+///
+/// A value keeps the tag it got first, so tags stay stable when saved with the map.
+/// A `{hash}` only depends on the key and the value; when another value's hash
+/// already renders to the same tag, the tag takes 4 more digits of the hash until
+/// it is free.
+pub(crate) fn tag_placeholder(
+    template: &str,
+    category: Category,
+    default: &str,
+    original: &str,
+    hash: Option<&str>,
+    tags: &mut HashMap<String, String>,
+    cache: &mut TagCache,
+) -> String {
+    let key = tag_key(default);
+    if let Some(tag) = tags.get(&key) {
+        return tag.clone();
+    }
+
+    let n = if template.contains("{n}") {
+        cache.next_number(template, category, tags)
+    } else {
+        1
+    };
+    let render = |hash_len: usize| {
+        VARIABLE_RE
+            .replace_all(template, |caps: &regex::Captures| match (&caps[1], hash) {
+                ("n", _) => n.to_string(),
+                ("category", _) => category.name().to_string(),
+                ("CATEGORY", _) => category.name().to_uppercase(),
+                ("default", _) => default.to_string(),
+                ("len", _) => original.chars().count().to_string(),
+                ("shape", _) => shape(original),
+                ("hash", Some(hash)) => hash[..hash_len.min(hash.len())].to_string(),
+                _ => caps[0].to_string(),
+            })
+            .into_owned()
+    };
+    let mut tag = render(HASH_LEN);
+
+    // Values that only differ in case or sigils share a hash, and so a tag
+    if let Some(hash) = hash.filter(|_| template.contains("{hash}")) {
+        let mut hash_len = HASH_LEN;
+        while let Some(other) = cache.hashes.get(&tag) {
+            if other == hash || hash_len >= hash.len() {
+                break;
+            }
+            hash_len += 4;
+            tag = render(hash_len);
+        }
+        cache
+            .hashes
+            .entry(tag.clone())
+            .or_insert_with(|| hash.to_string());
+    }
+    tags.insert(key, tag.clone());
+    cache.len = tags.len();
+    tag
+}
+
+// The highest `{n}` among the tags this template made for `category`
+fn highest_number(template: &str, category: Category, tags: &HashMap<String, String>) -> usize {
+    let mut pattern = String::from("^");
+    let mut last_end = 0;
+    for caps in VARIABLE_RE.captures_iter(template) {
+        let variable = caps.get(0).unwrap();
+        pattern.push_str(&regex::escape(&template[last_end..variable.start()]));
+        pattern.push_str(&match &caps[1] {
+            "n" => r"(\d+)".to_string(),
+            "category" => regex::escape(category.name()),
+            "CATEGORY" => regex::escape(&category.name().to_uppercase()),
+            _ => ".*?".to_string(),
+        });
+        last_end = variable.end();
+    }
+    pattern.push_str(&regex::escape(&template[last_end..]));
+    pattern.push('$');
+
+    // Only built from escaped literals and fixed groups
+    let regex = Regex::new(&pattern).unwrap();
+    tags.values()
+        .filter_map(|tag| regex.captures(tag)?.get(1)?.as_str().parse().ok())
+        .max()
+        .unwrap_or(0)
+}
+
+fn shape(original: &str) -> String {
+    original
+        .chars()
+        .map(|c| match c {
+            c if c.is_ascii_digit() => '9',
+            c if c.is_uppercase() => 'X',
+            c if c.is_alphabetic() => 'x',
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_placeholder() {
+        let mut tags = HashMap::new();
+        let mut cache = TagCache::default();
+        let mut tag = |template: &str, category: Category, default: &str, original: &str| {
+            tag_placeholder(
+                template, category, default, original, None, &mut tags, &mut cache,
+            )
+        };
+        let person = "<PERSON_{n}>";
+
+        assert_eq!(tag(person, Category::User, "@user1", "@jon"), "<PERSON_1>");
+        assert_eq!(tag(person, Category::User, "@user2", "@aria"), "<PERSON_2>");
+        // A display name reusing a handle's placeholder is the same person
+        assert_eq!(
+            tag(person, Category::DisplayName, "user1", "Jon Snow"),
+            "<PERSON_1>"
+        );
+        assert_eq!(
+            tag("<{CATEGORY}_{n}>", Category::Channel, "#ch1", "#general"),
+            "<CHANNEL_1>"
+        );
+        assert_eq!(
+            tag(
+                "[{category} {shape}]",
+                Category::Phone,
+                "+55 0001",
+                "+44 20-7946"
+            ),
+            "[phone +99 99-9999]"
+        );
+        assert_eq!(tags.len(), 4);
+    }
+
+    #[test]
+    fn test_tag_numbers_follow_the_map() {
+        let mut tags = HashMap::new();
+        let mut cache = TagCache::default();
+        let person = "<PERSON_{n}>";
+        let mut tag = |tags: &mut HashMap<String, String>, default: &str| {
+            tag_placeholder(person, Category::User, default, "", None, tags, &mut cache)
+        };
+
+        assert_eq!(tag(&mut tags, "@user1"), "<PERSON_1>");
+        assert_eq!(tag(&mut tags, "@user2"), "<PERSON_2>");
+        // Tags added elsewhere, e.g. by a loaded map
+        tags.insert("user9".to_string(), "<PERSON_9>".to_string());
+        assert_eq!(tag(&mut tags, "@user3"), "<PERSON_10>");
+    }
+
+    #[test]
+    fn test_hash_tags() {
+        let mut tags = HashMap::new();
+        let mut cache = TagCache::default();
+        let template = "@user-{hash}";
+        let mut tag = |default: &str, original: &str, hash: &str| {
+            tag_placeholder(
+                template,
+                Category::User,
+                default,
+                original,
+                Some(hash),
+                &mut tags,
+                &mut cache,
+            )
+        };
+
+        assert_eq!(
+            tag("@user1", "@jon", "7f3a9c01ab2e55"),
+            "@user-7f3a9c01ab2e"
+        );
+        // The same value in another case
+        assert_eq!(
+            tag("@user2", "@Jon", "7f3a9c01ab2e55"),
+            "@user-7f3a9c01ab2e"
+        );
+        // Another value with the same first digits gets more of them
+        assert_eq!(
+            tag("@user3", "@aria", "7f3a9c01ab2eff"),
+            "@user-7f3a9c01ab2eff"
+        );
+        assert_eq!(
+            tag("@user1", "@jon", "7f3a9c01ab2e55"),
+            "@user-7f3a9c01ab2e"
+        );
+    }
+
+    #[test]
+    fn test_validate_template() {
+        assert!(validate_template("<{CATEGORY}_{n}> {len}", false).is_ok());
+        assert!(validate_template("[REDACTED]", false).is_ok());
+        assert!(validate_template("<PERSON_{id}>", false).is_err());
+        assert!(validate_template("@user-{hash}", true).is_ok());
+        assert!(validate_template("@user-{hash}", false).is_err());
+        assert_eq!(
+            template_categories("all").unwrap().len(),
+            Category::ALL.len()
+        );
+        assert_eq!(
+            template_categories("display_name"),
+            Some(vec![Category::DisplayName])
+        );
+        assert_eq!(template_categories("person"), None);
+    }
+}